@@ -0,0 +1,216 @@
+use crate::error::Error;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The file names a `ConfigFile` is looked up with, in order.
+pub const CONFIG_FILE_NAMES: [&str; 2] = ["scout.toml", ".scout.toml"];
+
+/// This struct represents the cargo-scout configuration file.
+///
+/// Example `scout.toml`:
+/// ```toml
+/// # Only look at these files
+/// include = ["src/**"]
+/// # Never look at these files
+/// exclude = ["src/generated/**", "vendor/**"]
+/// # These files are generated, and will be skipped
+/// generated = ["**/*_pb.rs"]
+///
+/// # Settings for the `my-no-std-crate` workspace member
+/// [members.my-no-std-crate]
+/// no-default-features = true
+/// features = ["alloc"]
+///
+/// # Settings for the formatter
+/// [rustfmt]
+/// config-path = ".config/rustfmt.toml"
+/// config = ["max_width=120"]
+/// ```
+#[derive(Deserialize, Default, Debug, PartialEq)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ConfigFile {
+    /// Glob patterns of the files to include
+    pub include: Vec<String>,
+    /// Glob patterns of the files to exclude
+    pub exclude: Vec<String>,
+    /// Glob patterns of generated files
+    pub generated: Vec<String>,
+    /// Linter settings for some workspace members, by package name
+    pub members: BTreeMap<String, MemberConfig>,
+    /// Formatter settings
+    pub rustfmt: RustfmtConfig,
+}
+
+/// Linter settings overriding the command line ones for a workspace member.
+///
+/// Settings that are not set are left as they are.
+#[derive(Deserialize, Default, Debug, PartialEq, Clone)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct MemberConfig {
+    /// The features to enable
+    pub features: Option<Vec<String>>,
+    /// Whether the default features are disabled
+    pub no_default_features: Option<bool>,
+    /// Whether all the features are enabled
+    pub all_features: Option<bool>,
+    /// The targets to check, as cargo flags without dashes
+    /// Example:
+    /// `vec!["lib".to_string(), "tests".to_string()]`
+    pub targets: Option<Vec<String>>,
+    /// The lint groups to warn about
+    /// Example:
+    /// `vec!["clippy::all".to_string()]`
+    pub lints: Option<Vec<String>>,
+}
+
+/// Settings passed to rustfmt.
+#[derive(Deserialize, Default, Debug, PartialEq, Clone)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct RustfmtConfig {
+    /// The rustfmt configuration file, relative to the configuration file directory
+    pub config_path: Option<PathBuf>,
+    /// The edition to format with, instead of the edition of the crates
+    pub edition: Option<String>,
+    /// Rustfmt options, as `key=value` strings
+    /// Example:
+    /// `vec!["max_width=120".to_string()]`
+    pub config: Vec<String>,
+}
+
+impl ConfigFile {
+    /// This function will parse the configuration file at the given path.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn from_path(p: impl AsRef<Path>) -> Result<Self, Error> {
+        let toml = std::fs::read_to_string(&p).map_err(|source| Error::InvalidPath {
+            path: p.as_ref().to_path_buf(),
+            source,
+        })?;
+        Self::from_toml(&toml)
+    }
+
+    /// This function will look for a `scout.toml` or a `.scout.toml` file
+    /// in the given directory, and parse it.
+    ///
+    /// If there is no configuration file, it will return `ConfigFile::default()`.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn discover(dir: impl AsRef<Path>) -> Result<Self, Error> {
+        for name in &CONFIG_FILE_NAMES {
+            let path = dir.as_ref().join(name);
+            if path.is_file() {
+                return Self::from_path(path);
+            }
+        }
+        Ok(Self::default())
+    }
+
+    fn from_toml(s: &str) -> Result<Self, Error> {
+        Ok(toml::from_str(s)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConfigFile, MemberConfig, RustfmtConfig};
+    use crate::error::Error;
+    use std::fs;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_empty_config_file() {
+        assert_eq!(ConfigFile::default(), ConfigFile::from_toml("").unwrap());
+    }
+
+    #[test]
+    fn test_include_exclude() {
+        let config = ConfigFile::from_toml(
+            r#"include = ["src/**"]
+            exclude = ["src/generated/**", "vendor/**"]"#,
+        )
+        .unwrap();
+        assert_eq!(vec!["src/**"], config.include);
+        assert_eq!(vec!["src/generated/**", "vendor/**"], config.exclude);
+    }
+
+    #[test]
+    fn test_generated() {
+        let config = ConfigFile::from_toml(r#"generated = ["**/*_pb.rs"]"#).unwrap();
+        assert_eq!(vec!["**/*_pb.rs"], config.generated);
+    }
+
+    #[test]
+    fn test_members() {
+        let config = ConfigFile::from_toml(
+            r#"[members.no-std]
+            no-default-features = true
+            features = ["alloc"]
+            targets = ["lib"]
+
+            [members.legacy]
+            lints = ["clippy::all"]"#,
+        )
+        .unwrap();
+        let no_std = MemberConfig {
+            no_default_features: Some(true),
+            features: Some(vec!["alloc".to_string()]),
+            targets: Some(vec!["lib".to_string()]),
+            ..MemberConfig::default()
+        };
+        let legacy = MemberConfig {
+            lints: Some(vec!["clippy::all".to_string()]),
+            ..MemberConfig::default()
+        };
+        assert_eq!(Some(&no_std), config.members.get("no-std"));
+        assert_eq!(Some(&legacy), config.members.get("legacy"));
+        assert!(ConfigFile::from_toml("[members.foo]\nfoo = 1").is_err());
+    }
+
+    #[test]
+    fn test_rustfmt() {
+        let config = ConfigFile::from_toml(
+            r#"[rustfmt]
+            config-path = ".config/rustfmt.toml"
+            edition = "2021"
+            config = ["max_width=120"]"#,
+        )
+        .unwrap();
+        let rustfmt = RustfmtConfig {
+            config_path: Some(PathBuf::from(".config/rustfmt.toml")),
+            edition: Some("2021".to_string()),
+            config: vec!["max_width=120".to_string()],
+        };
+        assert_eq!(rustfmt, config.rustfmt);
+        assert!(ConfigFile::from_toml("[rustfmt]\nmax_width = 120").is_err());
+    }
+
+    #[test]
+    fn test_unknown_field() {
+        assert!(ConfigFile::from_toml("foo = 1").is_err());
+    }
+
+    #[test]
+    fn test_missing_config_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("scout.toml");
+        assert!(matches!(
+            ConfigFile::from_path(&path),
+            Err(Error::InvalidPath { path: p, .. }) if p == path
+        ));
+    }
+
+    #[test]
+    fn test_discover() {
+        let dir = TempDir::new().unwrap();
+        assert_eq!(
+            ConfigFile::default(),
+            ConfigFile::discover(dir.path()).unwrap()
+        );
+
+        fs::write(dir.path().join(".scout.toml"), r#"exclude = ["vendor/**"]"#).unwrap();
+        assert_eq!(
+            vec!["vendor/**"],
+            ConfigFile::discover(dir.path()).unwrap().exclude
+        );
+    }
+}
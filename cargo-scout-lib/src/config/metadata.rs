@@ -0,0 +1,356 @@
+use crate::config::{Config, Member, Target};
+use crate::error::Error;
+use crate::linter::toolchain::{command_error, output, Toolchain};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// This struct represents a Cargo project configuration,
+/// as resolved by `cargo metadata`.
+///
+/// Unlike `CargoConfig`, it handles glob members, excluded members,
+/// and workspaces with a root package, because cargo resolves them for us.
+#[derive(Debug)]
+pub struct MetadataConfig {
+    packages: Vec<Package>,
+    root: PathBuf,
+    target_dir: PathBuf,
+}
+
+/// A workspace member, as reported by `cargo metadata`.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Package {
+    /// The package name
+    /// Example:
+    /// "cargo-scout-lib".to_string()
+    pub name: String,
+    /// The package id, as used in cargo messages
+    pub id: String,
+    /// The absolute path of the package `Cargo.toml`
+    pub manifest_path: PathBuf,
+    /// The package targets (lib, bins, tests, ...)
+    pub targets: Vec<Target>,
+}
+
+#[derive(Deserialize)]
+struct Metadata {
+    packages: Vec<Package>,
+    workspace_members: Vec<String>,
+    workspace_root: PathBuf,
+    #[serde(default)]
+    target_directory: Option<PathBuf>,
+}
+
+impl Package {
+    /// The directory containing the package `Cargo.toml`.
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        self.manifest_path
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+    }
+
+    fn to_member(&self) -> Member {
+        Member {
+            name: self.name.clone(),
+            root: self.root().to_path_buf(),
+            manifest_path: self.manifest_path.clone(),
+            targets: self.targets.clone(),
+        }
+    }
+}
+
+impl Config for MetadataConfig {
+    fn members(&self) -> Vec<String> {
+        self.packages
+            .iter()
+            .map(|p| match p.root().strip_prefix(&self.root) {
+                Ok(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+                Ok(relative) => relative.to_string_lossy().to_string(),
+                Err(_) => p.root().to_string_lossy().to_string(),
+            })
+            .collect()
+    }
+
+    fn resolve_members(&self) -> Vec<Member> {
+        self.packages.iter().map(Package::to_member).collect()
+    }
+
+    fn root(&self) -> PathBuf {
+        self.root.clone()
+    }
+}
+
+impl MetadataConfig {
+    /// This function will instantiate a Config from a Cargo.toml path,
+    /// by running `cargo metadata --no-deps`.
+    ///
+    /// The members are all the packages of the workspace,
+    /// relative to the workspace root.
+    ///
+    /// # cargo-scout workspace example
+    /// ```
+    /// # use cargo_scout_lib::config::Config;
+    /// # use cargo_scout_lib::config::metadata::MetadataConfig;
+    /// let config = MetadataConfig::from_manifest_path("../Cargo.toml")?;
+    /// // We will lint `./cargo-scout`, `./cargo-scout-lib` and `./cargo-scout-macros`.
+    /// assert_eq!(
+    ///     vec!["cargo-scout", "cargo-scout-lib", "cargo-scout-macros"],
+    ///     config.members()
+    /// );
+    /// # Ok::<(), cargo_scout_lib::Error>(())
+    /// ```
+    #[allow(clippy::missing_errors_doc)]
+    pub fn from_manifest_path(p: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::from_manifest_path_with_toolchain(p, &Toolchain::default())
+    }
+
+    /// Like `from_manifest_path`, running `cargo metadata` with `toolchain`,
+    /// the one the linters run with.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn from_manifest_path_with_toolchain(
+        p: impl AsRef<Path>,
+        toolchain: &Toolchain,
+    ) -> Result<Self, Error> {
+        if !p.as_ref().is_file() {
+            return Err(Error::ManifestNotFound(p.as_ref().to_path_buf()));
+        }
+        let manifest_path = std::env::current_dir()?.join(p);
+        Self::from_metadata(&cargo_metadata(toolchain, &manifest_path)?)
+    }
+
+    /// This function will instantiate a Config from the output
+    /// of `cargo metadata --format-version 1`.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn from_metadata(metadata: &str) -> Result<Self, Error> {
+        let Metadata {
+            packages,
+            workspace_members,
+            workspace_root,
+            target_directory,
+        } = serde_json::from_str(metadata)?;
+        // Dependencies are listed as well when `--no-deps` is not used
+        let packages = packages
+            .into_iter()
+            .filter(|p| workspace_members.contains(&p.id))
+            .collect();
+        Ok(Self {
+            packages,
+            target_dir: target_directory.unwrap_or_else(|| workspace_root.join("target")),
+            root: workspace_root,
+        })
+    }
+
+    /// Only keeps the packages named in `packages` (or all of them if it is empty),
+    /// except the ones named in `exclude`,
+    /// like cargo's `--package` and `--exclude` flags.
+    ///
+    /// It returns the names of the skipped packages.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn select_packages(
+        &mut self,
+        packages: &[String],
+        exclude: &[String],
+    ) -> Result<Vec<String>, Error> {
+        if let Some(unknown) = packages
+            .iter()
+            .chain(exclude)
+            .find(|name| !self.packages.iter().any(|p| &p.name == *name))
+        {
+            return Err(Error::UnknownPackage(unknown.clone()));
+        }
+        let (selected, skipped): (Vec<Package>, Vec<Package>) =
+            self.packages.drain(..).partition(|p| {
+                (packages.is_empty() || packages.contains(&p.name)) && !exclude.contains(&p.name)
+            });
+        self.packages = selected;
+        Ok(skipped.into_iter().map(|p| p.name).collect())
+    }
+
+    /// The member packages of the workspace.
+    #[must_use]
+    pub fn packages(&self) -> &[Package] {
+        &self.packages
+    }
+
+    /// The target directory of the workspace,
+    /// honoring `CARGO_TARGET_DIR` and the `build.target-dir` setting.
+    #[must_use]
+    pub fn target_dir(&self) -> &Path {
+        &self.target_dir
+    }
+}
+
+// Skipped from code coverage
+// because an external command
+// cannot be easily unit tested
+#[cfg(not(tarpaulin_include))]
+fn cargo_metadata(toolchain: &Toolchain, manifest_path: &Path) -> Result<String, Error> {
+    // Like the linters, cargo runs in the directory of the manifest,
+    // where a toolchain file may select another toolchain
+    let dir = manifest_path.parent().unwrap_or(manifest_path);
+    let metadata = output(
+        toolchain
+            .select(dir)
+            .cargo()
+            .current_dir(dir)
+            .args(["metadata", "--no-deps", "--format-version", "1"])
+            .arg("--manifest-path")
+            .arg(manifest_path),
+    )?;
+    if metadata.status.success() {
+        return Ok(String::from_utf8(metadata.stdout)?);
+    }
+    // Cargo fails when it can't read or parse the manifests, which isn't a tool failure
+    match command_error(String::from_utf8(metadata.stderr)?) {
+        Error::Command(stderr) => Err(Error::InvalidManifest(stderr)),
+        e => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MetadataConfig;
+    use crate::config::Config;
+    use crate::error::Error;
+    use std::path::{Path, PathBuf};
+
+    // A workspace with a root package, and members matched by `crates/*`.
+    const WORKSPACE_METADATA: &str = r#"{
+        "packages": [
+            {
+                "name": "app",
+                "id": "app 0.1.0 (path+file:///project)",
+                "manifest_path": "/project/Cargo.toml",
+                "targets": [
+                    {"kind": ["bin"], "name": "app", "src_path": "/project/src/main.rs"},
+                    {"kind": ["test"], "name": "it", "src_path": "/project/tests/it.rs"}
+                ]
+            },
+            {
+                "name": "foo",
+                "id": "foo 0.1.0 (path+file:///project/crates/foo)",
+                "manifest_path": "/project/crates/foo/Cargo.toml",
+                "targets": [
+                    {"kind": ["lib"], "name": "foo", "src_path": "/project/crates/foo/src/lib.rs"},
+                    {"kind": ["bin"], "name": "foo-cli", "src_path": "/project/crates/foo/src/main.rs"},
+                    {"kind": ["bench"], "name": "foo-bench", "src_path": "/project/shared/foo/bench.rs"}
+                ]
+            },
+            {
+                "name": "serde",
+                "id": "serde 1.0.104 (registry+https://github.com/rust-lang/crates.io-index)",
+                "manifest_path": "/home/.cargo/registry/src/serde-1.0.104/Cargo.toml",
+                "targets": [
+                    {"kind": ["lib"], "name": "serde", "src_path": "/home/.cargo/registry/src/serde-1.0.104/src/lib.rs"}
+                ]
+            }
+        ],
+        "workspace_members": [
+            "app 0.1.0 (path+file:///project)",
+            "foo 0.1.0 (path+file:///project/crates/foo)"
+        ],
+        "workspace_root": "/project",
+        "target_directory": "/build/target"
+    }"#;
+
+    #[test]
+    fn test_members() {
+        let config = MetadataConfig::from_metadata(WORKSPACE_METADATA).unwrap();
+        assert_eq!(vec![".", "crates/foo"], config.members());
+        assert_eq!(PathBuf::from("/project"), config.root());
+        assert_eq!(Path::new("/build/target"), config.target_dir());
+        // Older cargo versions don't report it
+        let metadata = WORKSPACE_METADATA.replace(r#""target_directory": "/build/target""#, "");
+        let metadata = metadata.replace(
+            r#""workspace_root": "/project","#,
+            r#""workspace_root": "/project""#,
+        );
+        let config = MetadataConfig::from_metadata(&metadata).unwrap();
+        assert_eq!(Path::new("/project/target"), config.target_dir());
+    }
+
+    #[test]
+    fn test_resolve_members() {
+        let config = MetadataConfig::from_metadata(WORKSPACE_METADATA).unwrap();
+        let members = config.resolve_members();
+        assert_eq!(2, members.len());
+        let foo = &members[1];
+        assert_eq!("foo", foo.name);
+        assert_eq!(PathBuf::from("/project/crates/foo"), foo.root);
+        assert_eq!(
+            PathBuf::from("/project/crates/foo/Cargo.toml"),
+            foo.manifest_path
+        );
+        assert_eq!(3, foo.targets.len());
+        // The bench lives outside of the package directory
+        assert_eq!(
+            vec![
+                PathBuf::from("/project/crates/foo"),
+                PathBuf::from("/project/shared/foo")
+            ],
+            foo.dirs()
+        );
+    }
+
+    #[test]
+    fn test_packages() {
+        let config = MetadataConfig::from_metadata(WORKSPACE_METADATA).unwrap();
+        let names: Vec<&str> = config.packages().iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(vec!["app", "foo"], names);
+        assert_eq!(
+            PathBuf::from("/project/crates/foo/Cargo.toml"),
+            config.packages()[1].manifest_path
+        );
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| (*n).to_string()).collect()
+    }
+
+    #[test]
+    fn test_select_packages() {
+        let mut config = MetadataConfig::from_metadata(WORKSPACE_METADATA).unwrap();
+        let skipped = config.select_packages(&names(&["foo"]), &[]).unwrap();
+        assert_eq!(vec!["app"], skipped);
+        assert_eq!(vec!["crates/foo"], config.members());
+    }
+
+    #[test]
+    fn test_exclude_packages() {
+        let mut config = MetadataConfig::from_metadata(WORKSPACE_METADATA).unwrap();
+        let skipped = config.select_packages(&[], &names(&["foo"])).unwrap();
+        assert_eq!(vec!["foo"], skipped);
+        assert_eq!(vec!["."], config.members());
+    }
+
+    #[test]
+    fn test_select_all_packages() {
+        let mut config = MetadataConfig::from_metadata(WORKSPACE_METADATA).unwrap();
+        let skipped = config.select_packages(&[], &[]).unwrap();
+        assert!(skipped.is_empty());
+        assert_eq!(vec![".", "crates/foo"], config.members());
+    }
+
+    #[test]
+    fn test_select_unknown_package() {
+        let mut config = MetadataConfig::from_metadata(WORKSPACE_METADATA).unwrap();
+        // Dependencies are not members either
+        assert!(config.select_packages(&names(&["serde"]), &[]).is_err());
+        assert!(config.select_packages(&[], &names(&["bar"])).is_err());
+        // The configuration is left untouched
+        assert_eq!(vec![".", "crates/foo"], config.members());
+    }
+
+    #[test]
+    fn test_invalid_metadata() {
+        assert!(MetadataConfig::from_metadata("not json").is_err());
+    }
+
+    #[test]
+    fn test_manifest_not_found() {
+        assert!(matches!(
+            MetadataConfig::from_manifest_path("missing/Cargo.toml"),
+            Err(Error::ManifestNotFound(path)) if path == Path::new("missing/Cargo.toml")
+        ));
+    }
+}
@@ -1,18 +1,137 @@
+use crate::utils::normalize_path;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+pub mod file;
+pub mod metadata;
 pub mod rust;
 
+/// A member of the project, which is linted on its own.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Member {
+    /// The package name
+    /// Example:
+    /// "cargo-scout-lib".to_string()
+    pub name: String,
+    /// The member directory, absolute or relative to `Config::root`
+    pub root: PathBuf,
+    /// The path of the member `Cargo.toml`, absolute or relative to `Config::root`
+    pub manifest_path: PathBuf,
+    /// The member targets (lib, bins, tests, ...)
+    pub targets: Vec<Target>,
+}
+
+/// A target of a member, such as a library or a binary.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Target {
+    /// The target name
+    pub name: String,
+    /// The target kinds
+    /// Example:
+    /// `vec!["lib".to_string()]`
+    pub kind: Vec<String>,
+    /// The path of the target entry point, absolute or relative to `Config::root`
+    /// Example:
+    /// "/path/to/project/src/lib.rs"
+    pub src_path: PathBuf,
+    /// The Rust edition of the target
+    /// Example:
+    /// `Some("2018".to_string())`
+    #[serde(default)]
+    pub edition: Option<String>,
+}
+
+impl Member {
+    /// Builds a member out of its directory, relative to `root`.
+    ///
+    /// The member is assumed to be a package with a `Cargo.toml` file in this directory,
+    /// named after the directory. Its targets are unknown.
+    pub fn from_dir(root: impl AsRef<Path>, dir: impl AsRef<Path>) -> Self {
+        let dir = dir.as_ref();
+        let name = std::fs::canonicalize(root.as_ref().join(dir))
+            .ok()
+            .and_then(|path| path.file_name().map(|n| n.to_string_lossy().to_string()))
+            .unwrap_or_else(|| dir.to_string_lossy().to_string());
+        Self {
+            name,
+            root: dir.to_path_buf(),
+            manifest_path: dir.join("Cargo.toml"),
+            targets: Vec::new(),
+        }
+    }
+
+    /// Returns the member with its relative paths joined onto `root`, and normalized.
+    #[must_use]
+    pub fn anchored_on(&self, root: impl AsRef<Path>) -> Self {
+        let root = root.as_ref();
+        Self {
+            name: self.name.clone(),
+            root: normalize_path(root.join(&self.root)),
+            manifest_path: normalize_path(root.join(&self.manifest_path)),
+            targets: self
+                .targets
+                .iter()
+                .map(|t| Target {
+                    src_path: normalize_path(root.join(&t.src_path)),
+                    ..t.clone()
+                })
+                .collect(),
+        }
+    }
+
+    /// The directories the member owns: its own directory,
+    /// and the directories of the targets living outside of it
+    /// (e.g. with `path = "../shared/lib.rs"`).
+    #[must_use]
+    pub fn dirs(&self) -> Vec<PathBuf> {
+        let mut dirs = vec![self.root.clone()];
+        for target in &self.targets {
+            if let Some(dir) = target.src_path.parent() {
+                if !dirs.iter().any(|d| dir.starts_with(d)) {
+                    dirs.push(dir.to_path_buf());
+                }
+            }
+        }
+        dirs
+    }
+
+    /// The edition of the target `path` belongs to,
+    /// which is the one with the closest entry point directory.
+    ///
+    /// Paths outside of the target directories get the edition of the first target.
+    #[must_use]
+    pub fn edition_of(&self, path: impl AsRef<Path>) -> Option<&str> {
+        let path = path.as_ref();
+        self.targets
+            .iter()
+            .enumerate()
+            .filter_map(|(i, t)| {
+                t.src_path
+                    .parent()
+                    .filter(|dir| path.starts_with(dir))
+                    .map(|dir| (dir.components().count(), i))
+            })
+            // On a tie, the first target wins
+            .max_by(|(a, i), (b, j)| a.cmp(b).then(j.cmp(i)))
+            .map(|(_, i)| &self.targets[i])
+            .or_else(|| self.targets.first())
+            .and_then(|t| t.edition.as_deref())
+    }
+}
+
 /// This trait is responsible for providing a list of members,
 /// which are directories to be linted against.
 pub trait Config {
-    /// This function should return a list of relative paths
+    /// This function should return a list of paths relative to `root`
     /// a linter will iterate on.
     ///
-    /// If only the current working directory must be checked, it must return `vec![".".to_string()]`
+    /// If only the root directory must be checked, it must return `vec![".".to_string()]`
     ///
     /// If several directories must be checked,
     /// return their relative path as strings.
     ///
-    /// For example if your current working directory is `foo`,
-    /// and you want to check `./bar` and `./baz`,
+    /// For example if `root` is `foo`,
+    /// and you want to check `foo/bar` and `foo/baz`,
     /// return `vec!["bar".to_string(), "baz".to_string()]`
     ///
     /// # Example with the root directory
@@ -31,7 +150,7 @@ pub trait Config {
     /// #    }
     /// # }
     /// let config = CustomConfig::new();
-    /// // Only the current directory must be linted
+    /// // Only the root directory must be linted
     /// assert_eq!(vec![".".to_string()], config.members());
     /// ```
     ///
@@ -51,7 +170,7 @@ pub trait Config {
     /// #    }
     /// # }
     /// let config = CustomConfig::new();
-    /// // Directories ./foo and ./bar must be linted
+    /// // Directories `foo` and `bar` of the root directory must be linted
     /// assert_eq!(vec!["foo".to_string(), "bar".to_string()], config.members());
     /// ```
     ///
@@ -75,4 +194,107 @@ pub trait Config {
     /// }
     /// ```
     fn members(&self) -> Vec<String>;
+
+    /// This function should return the members with their package information:
+    /// name, manifest and targets.
+    ///
+    /// It defaults to building a `Member` out of each directory returned by `members`,
+    /// so existing `Config` implementations keep working.
+    fn resolve_members(&self) -> Vec<Member> {
+        let root = self.root();
+        self.members()
+            .iter()
+            .map(|m| Member::from_dir(&root, m))
+            .collect()
+    }
+
+    /// This function should return the directory members are relative to,
+    /// which is usually the directory of the project manifest.
+    ///
+    /// Lint locations with a relative path are relative to this directory as well.
+    ///
+    /// It defaults to the current working directory.
+    fn root(&self) -> PathBuf {
+        PathBuf::from(".")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, Member, Target};
+    use std::path::PathBuf;
+
+    struct DirConfig {}
+
+    impl Config for DirConfig {
+        fn members(&self) -> Vec<String> {
+            vec!["src".to_string(), "does/not/exist".to_string()]
+        }
+    }
+
+    fn target(src_path: &str) -> Target {
+        Target {
+            name: "foo".to_string(),
+            kind: vec!["lib".to_string()],
+            src_path: PathBuf::from(src_path),
+            edition: Some("2018".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_default_resolve_members() {
+        let members = DirConfig {}.resolve_members();
+        assert_eq!(2, members.len());
+        assert_eq!("src", members[0].name);
+        assert_eq!(PathBuf::from("src"), members[0].root);
+        assert_eq!(PathBuf::from("src/Cargo.toml"), members[0].manifest_path);
+        assert!(members[0].targets.is_empty());
+        // The name falls back to the directory
+        assert_eq!("does/not/exist", members[1].name);
+    }
+
+    #[test]
+    fn test_member_dirs() {
+        let mut member = Member::from_dir(".", "crates/foo");
+        member.targets = vec![
+            target("crates/foo/src/lib.rs"),
+            target("crates/foo/benches/bench.rs"),
+            target("shared/foo/lib.rs"),
+        ];
+        assert_eq!(
+            vec![PathBuf::from("crates/foo"), PathBuf::from("shared/foo")],
+            member.dirs()
+        );
+    }
+
+    #[test]
+    fn test_member_anchored_on() {
+        let mut member = Member::from_dir(".", ".");
+        member.targets = vec![target("src/lib.rs"), target("/elsewhere/lib.rs")];
+        let member = member.anchored_on("/project");
+        assert_eq!(PathBuf::from("/project"), member.root);
+        assert_eq!(PathBuf::from("/project/Cargo.toml"), member.manifest_path);
+        assert_eq!(
+            PathBuf::from("/project/src/lib.rs"),
+            member.targets[0].src_path
+        );
+        assert_eq!(
+            PathBuf::from("/elsewhere/lib.rs"),
+            member.targets[1].src_path
+        );
+    }
+
+    #[test]
+    fn test_edition_of() {
+        let mut member = Member::from_dir(".", "foo");
+        assert_eq!(None, member.edition_of("foo/src/lib.rs"));
+        let mut tests = target("foo/tests/it.rs");
+        tests.edition = Some("2021".to_string());
+        member.targets = vec![target("foo/src/lib.rs"), tests, target("foo/build.rs")];
+        assert_eq!(Some("2018"), member.edition_of("foo/src/module/mod.rs"));
+        assert_eq!(Some("2021"), member.edition_of("foo/tests/common/mod.rs"));
+        // `foo/build.rs` is the closest target
+        assert_eq!(Some("2018"), member.edition_of("foo/examples/example.rs"));
+        assert_eq!(Some("2018"), member.edition_of("elsewhere/lib.rs"));
+    }
 }
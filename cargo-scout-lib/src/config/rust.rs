@@ -1,9 +1,13 @@
 use crate::config::Config;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// This struct represents a Cargo project configuration.
+///
+/// Workspace members are read verbatim from the manifest:
+/// see `MetadataConfig` for a configuration resolved by cargo.
 pub struct CargoConfig {
     members: Vec<String>,
+    root: PathBuf,
 }
 
 impl Config for CargoConfig {
@@ -11,6 +15,10 @@ impl Config for CargoConfig {
     fn members(&self) -> Vec<String> {
         self.members.clone()
     }
+
+    fn root(&self) -> PathBuf {
+        self.root.clone()
+    }
 }
 
 impl CargoConfig {
@@ -36,22 +44,34 @@ impl CargoConfig {
     /// # use cargo_scout_lib::config::Config;
     /// # use cargo_scout_lib::config::rust::CargoConfig;
     /// let config = CargoConfig::from_manifest_path("../Cargo.toml")?;
-    /// // We will lint `./cargo-scout` and `./cargo-scout-lib`.
-    /// assert_eq!(vec!["cargo-scout".to_string(), "cargo-scout-lib".to_string()], config.members());
+    /// // We will lint `./cargo-scout`, `./cargo-scout-lib` and `./cargo-scout-macros`.
+    /// assert_eq!(vec!["cargo-scout", "cargo-scout-lib", "cargo-scout-macros"], config.members());
     /// # Ok::<(), cargo_scout_lib::Error>(())
     /// ```
     #[allow(clippy::missing_errors_doc)]
     pub fn from_manifest_path(p: impl AsRef<Path>) -> Result<Self, crate::error::Error> {
-        Ok(Self::from_manifest(cargo_toml::Manifest::from_path(p)?))
+        let mut config = Self::from_manifest(cargo_toml::Manifest::from_path(&p)?);
+        // Members are relative to the manifest directory
+        if let Some(root) = p.as_ref().parent() {
+            if !root.as_os_str().is_empty() {
+                config.root = root.to_path_buf();
+            }
+        }
+        Ok(config)
     }
 
     fn from_manifest(m: cargo_toml::Manifest) -> Self {
+        let root = PathBuf::from(".");
         if let Some(w) = m.workspace {
-            Self { members: w.members }
+            Self {
+                members: w.members,
+                root,
+            }
         } else {
             Self {
                 // Project root only
                 members: vec![".".to_string()],
+                root,
             }
         }
     }
@@ -61,6 +81,7 @@ impl CargoConfig {
 mod tests {
     use crate::config::rust::CargoConfig;
     use crate::config::Config;
+    use std::path::PathBuf;
 
     #[test]
     fn test_not_workspace_manifest() {
@@ -75,6 +96,14 @@ mod tests {
         let config = CargoConfig::from_manifest_path("Cargo.toml").unwrap();
         assert_eq!(vec!["."], config.members());
     }
+    #[test]
+    fn test_root() {
+        let config = CargoConfig::from_manifest_path("Cargo.toml").unwrap();
+        assert_eq!(PathBuf::from("."), config.root());
+        let config = CargoConfig::from_manifest_path("../Cargo.toml").unwrap();
+        assert_eq!(PathBuf::from(".."), config.root());
+    }
+
     #[test]
     fn test_neqo_members_manifest() {
         let neqo_toml = r#"[workspace]
@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -18,4 +19,106 @@ pub enum Error {
     Io(#[from] std::io::Error),
     #[error("Git error: {0}")]
     Git(#[from] git2::Error),
+    #[error("Glob error: {0}")]
+    Glob(#[from] globset::Error),
+    #[error("Invalid date: {0}, expected YYYY-MM-DD or a duration such as `2 weeks`")]
+    InvalidDate(String),
+    #[error("Toml error: {0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("Package error: {0} is not a member of the workspace")]
+    UnknownPackage(String),
+    #[error("Replacement error: {0}")]
+    Replacement(String),
+    #[error("Compilation error: {0} error(s) found")]
+    Compilation(usize),
+    #[error("Member error: {0} member(s) failed to lint")]
+    MemberFailures(usize),
+    #[error("Tool error: `{0}` was not found")]
+    ToolNotFound(String),
+    #[error("Component error: {component} is not installed")]
+    ComponentMissing {
+        component: String,
+        /// The toolchain missing the component, if known
+        toolchain: Option<String>,
+    },
+    #[error("Toolchain error: {0} is not installed")]
+    ToolchainMissing(String),
+    #[error("Revision error: {0} is not a revision of the repository")]
+    InvalidRevision(String),
+    #[error("Manifest error: {} does not exist", .0.display())]
+    ManifestNotFound(PathBuf),
+    #[error("Manifest error: {0}")]
+    InvalidManifest(String),
+    #[error("Path error: {}: {source}", .path.display())]
+    InvalidPath {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+impl Error {
+    /// What to do to fix the error, if there's more to it than its message.
+    #[must_use]
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            Self::ToolNotFound(tool) => Some(format!(
+                "make sure `{}` is in your PATH, or install Rust with rustup: https://rustup.rs",
+                tool
+            )),
+            Self::ComponentMissing {
+                component,
+                toolchain: Some(toolchain),
+            } => Some(format!(
+                "run `rustup component add {} --toolchain {}`",
+                component, toolchain
+            )),
+            Self::ComponentMissing {
+                component,
+                toolchain: None,
+            } => Some(format!("run `rustup component add {}`", component)),
+            Self::ToolchainMissing(toolchain) => {
+                Some(format!("run `rustup toolchain install {}`", toolchain))
+            }
+            Self::InvalidRevision(_) => Some(
+                "pass an existing branch, tag or commit with `--branch`, or fetch it first"
+                    .to_string(),
+            ),
+            Self::ManifestNotFound(_) => Some(
+                "run cargo-scout in a cargo project, or pass its `Cargo.toml` with `--cargo-toml`"
+                    .to_string(),
+            ),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Error;
+
+    #[test]
+    fn test_hint() {
+        let error = Error::ComponentMissing {
+            component: "clippy".to_string(),
+            toolchain: Some("nightly".to_string()),
+        };
+        assert_eq!(
+            "Component error: clippy is not installed",
+            error.to_string()
+        );
+        assert_eq!(
+            Some("run `rustup component add clippy --toolchain nightly`".to_string()),
+            error.hint()
+        );
+        assert_eq!(
+            Some("run `rustup toolchain install beta`".to_string()),
+            Error::ToolchainMissing("beta".to_string()).hint()
+        );
+        assert_eq!(None, Error::NotClean.hint());
+        let error = Error::InvalidPath {
+            path: "missing".into(),
+            source: std::io::ErrorKind::NotFound.into(),
+        };
+        assert_eq!("Path error: missing: entity not found", error.to_string());
+    }
 }
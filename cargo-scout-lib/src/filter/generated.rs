@@ -0,0 +1,181 @@
+use super::glob_set;
+use crate::error::Error;
+use globset::GlobSet;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Component, Path, PathBuf};
+
+/// The marker generated files begin with.
+const GENERATED_MARKER: &str = "@generated";
+
+/// How many lines of a file header are looked at for the marker.
+const HEADER_LINES: usize = 20;
+
+/// This struct detects generated files, which should not be linted.
+///
+/// A file is considered generated if:
+/// - it matches one of the configured glob patterns,
+/// - it lives in a cargo target directory (e.g. `build.rs` outputs in `OUT_DIR`),
+/// - or its header comments contain an `@generated` marker.
+#[derive(Default, Clone, Debug)]
+pub struct GeneratedFiles {
+    patterns: Option<GlobSet>,
+    // Files are checked for every section and every lint,
+    // so their headers are only read once.
+    markers: RefCell<HashMap<PathBuf, bool>>,
+}
+
+impl GeneratedFiles {
+    /// Builds a `GeneratedFiles` detector with additional glob patterns,
+    /// matched against paths relative to the project root.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn new(patterns: &[String]) -> Result<Self, Error> {
+        Ok(Self {
+            patterns: glob_set(patterns)?,
+            ..Self::default()
+        })
+    }
+
+    /// `path` is the file path relative to `root`, or an absolute path.
+    #[must_use]
+    pub fn is_generated(&self, root: impl AsRef<Path>, path: impl AsRef<Path>) -> bool {
+        let root = root.as_ref();
+        let path = path.as_ref();
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        self.patterns.as_ref().is_some_and(|p| p.is_match(relative))
+            || in_target_dir(root, path)
+            || self.has_generated_marker(root.join(path))
+    }
+
+    fn has_generated_marker(&self, path: PathBuf) -> bool {
+        if let Some(generated) = self.markers.borrow().get(&path) {
+            return *generated;
+        }
+        let generated = has_generated_marker(&path);
+        self.markers.borrow_mut().insert(path, generated);
+        generated
+    }
+}
+
+fn in_target_dir(root: &Path, path: &Path) -> bool {
+    let absolute = root.join(path);
+    let mut env_dirs = ["CARGO_TARGET_DIR", "OUT_DIR"]
+        .iter()
+        .filter_map(std::env::var_os)
+        .map(|dir| root.join(dir));
+    if absolute.starts_with(root.join("target")) || env_dirs.any(|dir| absolute.starts_with(dir)) {
+        return true;
+    }
+    // `build.rs` outputs live in `<target dir>/[<triple>/]<profile>/build/<package>-<hash>/out`
+    let components: Vec<Component> = absolute.components().collect();
+    components.windows(3).enumerate().any(|(i, w)| {
+        w[0] == Component::Normal("build".as_ref())
+            && w[2] == Component::Normal("out".as_ref())
+            && (i >= 2 && is_target_dir(&components[..i - 1])
+                || i >= 3 && is_target_dir(&components[..i - 2]))
+    })
+}
+
+// Cargo tags the target directories it creates
+fn is_target_dir(components: &[Component]) -> bool {
+    let dir: PathBuf = components.iter().collect();
+    dir.file_name() == Some("target".as_ref()) || dir.join("CACHEDIR.TAG").is_file()
+}
+
+// Only the leading comments are looked at,
+// so a marker in the middle of the code doesn't count.
+fn has_generated_marker(path: impl AsRef<Path>) -> bool {
+    let Ok(file) = File::open(path) else {
+        return false;
+    };
+    for line in BufReader::new(file).lines().take(HEADER_LINES) {
+        let Ok(line) = line else {
+            return false;
+        };
+        let line = line.trim();
+        if line.contains(GENERATED_MARKER) {
+            return true;
+        }
+        if !(line.is_empty()
+            || line.starts_with("//")
+            || line.starts_with("/*")
+            || line.starts_with('*')
+            || line.starts_with("#!"))
+        {
+            return false;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GeneratedFiles;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_patterns() {
+        let generated = GeneratedFiles::new(&["**/*_pb.rs".to_string()]).unwrap();
+        assert!(generated.is_generated("/foo", "src/proto/message_pb.rs"));
+        assert!(generated.is_generated("/foo", "/foo/src/message_pb.rs"));
+        assert!(!generated.is_generated("/foo", "src/lib.rs"));
+    }
+
+    #[test]
+    fn test_target_dir() {
+        let generated = GeneratedFiles::default();
+        assert!(generated.is_generated("/foo", "target/debug/build/bar-1234/out/bindings.rs"));
+        assert!(generated.is_generated("/foo", "/foo/target/debug/foo.rs"));
+        assert!(generated.is_generated("/foo", "/tmp/target/debug/build/bar-1234/out/x.rs"));
+        assert!(!generated.is_generated("/foo", "src/build/out.rs"));
+        assert!(!generated.is_generated("/foo", "tools/build/codegen/out/mod.rs"));
+        assert!(!generated.is_generated("/foo", "src/target.rs"));
+    }
+
+    #[test]
+    fn test_custom_target_dir() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("out")).unwrap();
+        fs::write(dir.path().join("out").join("CACHEDIR.TAG"), "").unwrap();
+
+        let generated = GeneratedFiles::default();
+        assert!(generated.is_generated(dir.path(), "out/debug/build/bar-1234/out/x.rs"));
+        assert!(generated.is_generated(
+            dir.path(),
+            "out/x86_64-unknown-linux-gnu/debug/build/bar-1234/out/x.rs"
+        ));
+        assert!(!generated.is_generated(dir.path(), "tools/build/codegen/out/mod.rs"));
+    }
+
+    #[test]
+    fn test_generated_marker() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("generated.rs"),
+            "// This file is @generated by a tool.\n// Do not edit.\n\npub fn foo() {}\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("doc_generated.rs"),
+            "//! Bindings\n/*\n * @generated\n */\npub fn foo() {}\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("not_generated.rs"),
+            "pub fn foo() {}\n// @generated is only relevant in the header\n",
+        )
+        .unwrap();
+
+        let generated = GeneratedFiles::default();
+        assert!(generated.is_generated(dir.path(), "generated.rs"));
+        assert!(generated.is_generated(dir.path(), "doc_generated.rs"));
+        assert!(!generated.is_generated(dir.path(), "not_generated.rs"));
+        assert!(!generated.is_generated(dir.path(), "does_not_exist.rs"));
+        // The result is cached per file
+        fs::write(dir.path().join("generated.rs"), "pub fn foo() {}\n").unwrap();
+        assert!(generated.is_generated(dir.path(), "generated.rs"));
+    }
+}
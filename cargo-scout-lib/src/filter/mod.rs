@@ -0,0 +1,108 @@
+use crate::error::Error;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::path::Path;
+
+pub mod generated;
+
+/// This struct decides which files are relevant,
+/// based on include and exclude glob patterns.
+///
+/// A file is relevant if it matches at least one include pattern
+/// (or if there are no include patterns), and no exclude pattern.
+///
+/// Patterns are matched against paths relative to the project root.
+///
+/// # Example
+/// ```
+/// # use cargo_scout_lib::filter::PathFilter;
+/// let filter = PathFilter::new(&["src/**".to_string()], &["src/generated/**".to_string()])?;
+/// assert!(filter.is_match("src/lib.rs"));
+/// assert!(!filter.is_match("src/generated/bindings.rs"));
+/// assert!(!filter.is_match("benches/bench.rs"));
+/// # Ok::<(), cargo_scout_lib::Error>(())
+/// ```
+#[derive(Default, Clone, Debug)]
+pub struct PathFilter {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl PathFilter {
+    /// Builds a `PathFilter` from include and exclude glob patterns.
+    ///
+    /// An empty list of include patterns includes every file.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self, Error> {
+        Ok(Self {
+            include: glob_set(include)?,
+            exclude: glob_set(exclude)?,
+        })
+    }
+
+    #[must_use]
+    pub fn is_match(&self, path: impl AsRef<Path>) -> bool {
+        let path = path.as_ref();
+        let included = self.include.as_ref().map_or(true, |i| i.is_match(path));
+        let excluded = self.exclude.as_ref().is_some_and(|e| e.is_match(path));
+        included && !excluded
+    }
+}
+
+fn glob_set(patterns: &[String]) -> Result<Option<GlobSet>, Error> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern)?);
+    }
+    Ok(Some(builder.build()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PathFilter;
+
+    fn patterns(p: &[&str]) -> Vec<String> {
+        p.iter().map(|s| (*s).to_string()).collect()
+    }
+
+    #[test]
+    fn test_empty_filter_matches_everything() {
+        let filter = PathFilter::default();
+        assert!(filter.is_match("src/lib.rs"));
+        assert!(filter.is_match("foo/bar/baz.rs"));
+    }
+
+    #[test]
+    fn test_include() {
+        let filter = PathFilter::new(&patterns(&["src/**", "tests/*.rs"]), &[]).unwrap();
+        assert!(filter.is_match("src/lib.rs"));
+        assert!(filter.is_match("src/foo/bar.rs"));
+        assert!(filter.is_match("tests/integration.rs"));
+        assert!(!filter.is_match("vendor/foo/src/lib.rs"));
+        assert!(!filter.is_match("benches/bench.rs"));
+    }
+
+    #[test]
+    fn test_exclude() {
+        let filter = PathFilter::new(&[], &patterns(&["vendor/**", "**/generated/*.rs"])).unwrap();
+        assert!(filter.is_match("src/lib.rs"));
+        assert!(!filter.is_match("vendor/foo/src/lib.rs"));
+        assert!(!filter.is_match("src/generated/bindings.rs"));
+        assert!(!filter.is_match("foo/src/generated/bindings.rs"));
+    }
+
+    #[test]
+    fn test_exclude_wins_over_include() {
+        let filter =
+            PathFilter::new(&patterns(&["src/**"]), &patterns(&["src/fixtures/**"])).unwrap();
+        assert!(filter.is_match("src/lib.rs"));
+        assert!(!filter.is_match("src/fixtures/foo.rs"));
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        assert!(PathFilter::new(&patterns(&["src/[.rs"]), &[]).is_err());
+    }
+}
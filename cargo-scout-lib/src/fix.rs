@@ -0,0 +1,447 @@
+use crate::error::Error;
+use crate::linter::{Lint, Replacement};
+use crate::utils::paths_match;
+use crate::vcs::Section;
+use cargo_scout_macros::warn;
+use colored::Colorize;
+use similar::TextDiff;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// A file, before and after its replacements are applied.
+#[derive(PartialEq, Clone, Debug)]
+pub struct FixedFile {
+    /// The path of the file, relative to the root the lints are relative to
+    pub path: PathBuf,
+    pub original: String,
+    pub fixed: String,
+}
+
+/// Applies the replacements suggested for `lints`,
+/// `root` being the directory their paths are relative to.
+///
+/// Only the replaced lines change, the rest of the files is left byte for byte identical.
+/// It returns the paths of the files that have been changed.
+#[allow(clippy::missing_errors_doc)]
+pub fn apply(root: impl AsRef<Path>, lints: &[Lint]) -> Result<Vec<PathBuf>, Error> {
+    let mut changed_files = Vec::new();
+    for file in fix(&root, lints)? {
+        std::fs::write(root.as_ref().join(&file.path), file.fixed)?;
+        changed_files.push(file.path);
+    }
+    Ok(changed_files)
+}
+
+/// Returns the files the replacements suggested for `lints` would change,
+/// without writing them.
+///
+/// Files whose content doesn't match the replacements anymore are skipped with a warning.
+#[allow(clippy::missing_errors_doc)]
+pub fn fix(root: impl AsRef<Path>, lints: &[Lint]) -> Result<Vec<FixedFile>, Error> {
+    let mut replacements: BTreeMap<&Path, Vec<&Replacement>> = BTreeMap::new();
+    for lint in lints.iter().filter(|l| !l.replacements.is_empty()) {
+        replacements
+            .entry(&lint.location.path)
+            .or_default()
+            .extend(&lint.replacements);
+    }
+    let mut fixed_files = Vec::new();
+    for (path, file_replacements) in replacements {
+        let original = std::fs::read_to_string(root.as_ref().join(path))?;
+        // The file may have changed since it was linted, or have CRLF line endings,
+        // which the replacements don't have: the other files can still be fixed.
+        let fixed = match apply_to(path, &original, file_replacements) {
+            Ok(fixed) => fixed,
+            Err(e) => {
+                warn!("[Fix] - skipping {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        if fixed != original {
+            fixed_files.push(FixedFile {
+                path: path.to_path_buf(),
+                original,
+                fixed,
+            });
+        }
+    }
+    Ok(fixed_files)
+}
+
+/// Renders the changes of `files` as a unified diff,
+/// which can be applied with `git apply` from the root their paths are relative to.
+#[must_use]
+pub fn patch(files: &[FixedFile]) -> String {
+    let mut diff = String::new();
+    for file in files {
+        // Git style paths use forward slashes
+        let path = file.path.to_string_lossy().replace('\\', "/");
+        diff.push_str(
+            &TextDiff::from_lines(&file.original, &file.fixed)
+                .unified_diff()
+                .header(&format!("a/{}", path), &format!("b/{}", path))
+                .to_string(),
+        );
+    }
+    diff
+}
+
+/// Drops the replacements of `lints` touching lines outside of the diff `sections`,
+/// so fixes never change code that hasn't been changed.
+///
+/// An insertion is kept if it is next to changed lines.
+#[must_use]
+pub fn in_sections(lints: &[Lint], sections: &[Section]) -> Vec<Lint> {
+    lints
+        .iter()
+        .map(|lint| {
+            let file_sections: Vec<&Section> = sections
+                .iter()
+                .filter(|s| paths_match(&lint.location.path, &s.file_name))
+                .collect();
+            let mut lint = lint.clone();
+            lint.replacements
+                .retain(|r| replaced_lines_changed(r, &file_sections));
+            lint
+        })
+        .collect()
+}
+
+fn replaced_lines_changed(replacement: &Replacement, sections: &[&Section]) -> bool {
+    let line_count = replacement.original.matches('\n').count();
+    if line_count == 0 {
+        return sections
+            .iter()
+            .any(|s| s.line_start <= replacement.line && replacement.line <= s.line_end);
+    }
+    (replacement.line..).take(line_count).all(|line| {
+        sections
+            .iter()
+            .any(|s| s.line_start <= line && line < s.line_end)
+    })
+}
+
+// Replacements are applied from the top of the file,
+// on the original content, so their line numbers stay valid.
+fn apply_to(path: &Path, content: &str, replacements: Vec<&Replacement>) -> Result<String, Error> {
+    // The same lint may be reported for several targets,
+    // interleaved with other lints
+    let mut replacements = replacements.into_iter().fold(Vec::new(), |mut unique, r| {
+        if !unique.contains(&r) {
+            unique.push(r);
+        }
+        unique
+    });
+    replacements.sort_by_key(|r| (r.line, r.original.len()));
+    // Lines are compared as if they all ended with a newline,
+    // the replacements of the last line add the missing one.
+    let missing_newline = !content.is_empty() && !content.ends_with('\n');
+    let content = if missing_newline {
+        format!("{}\n", content)
+    } else {
+        content.to_string()
+    };
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let mut fixed = String::with_capacity(content.len());
+    let mut applied_until = 0;
+    for r in replacements {
+        let start = r
+            .line
+            .checked_sub(1)
+            .and_then(|i| line_starts.get(i as usize))
+            .copied()
+            .filter(|start| content[*start..].starts_with(&r.original))
+            .ok_or_else(|| out_of_date(path, r))?;
+        if start < applied_until {
+            warn!(
+                "[Fix] - skipping a replacement overlapping another one in {} at line {}",
+                path.display(),
+                r.line
+            );
+            continue;
+        }
+        fixed.push_str(&content[applied_until..start]);
+        fixed.push_str(&r.replacement);
+        applied_until = start + r.original.len();
+    }
+    fixed.push_str(&content[applied_until..]);
+    if missing_newline {
+        fixed.pop();
+    }
+    Ok(fixed)
+}
+
+fn out_of_date(path: &Path, replacement: &Replacement) -> Error {
+    Error::Replacement(format!(
+        "{} changed at line {} since it was linted",
+        path.display(),
+        replacement.line
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply, apply_to, fix, in_sections, patch, FixedFile, Lint, Replacement, Section};
+    use crate::linter::Location;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use tempfile::TempDir;
+
+    fn replacement(line: u32, original: &str, replacement: &str) -> Replacement {
+        Replacement {
+            line,
+            original: original.to_string(),
+            replacement: replacement.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_apply_to() {
+        let content = "fn a( ) {}\r\nfn b() {\n    let x = 1; let y = 2;\n}\n";
+        let replacements = [
+            replacement(
+                3,
+                "    let x = 1; let y = 2;\n",
+                "    let x = 1;\n    let y = 2;\n",
+            ),
+            replacement(2, "", "\n"),
+        ];
+        // The first line is not replaced, and keeps its line ending
+        assert_eq!(
+            "fn a( ) {}\r\n\nfn b() {\n    let x = 1;\n    let y = 2;\n}\n",
+            apply_to(Path::new("lib.rs"), content, replacements.iter().collect()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_apply_to_last_line() {
+        let path = Path::new("lib.rs");
+        let content = "fn a() {}\nfn b( ) {}";
+        // Rustfmt adds the missing newline at the end of the file
+        let newline = replacement(3, "", "\n");
+        assert_eq!(
+            "fn a() {}\nfn b( ) {}\n",
+            apply_to(path, content, vec![&newline]).unwrap()
+        );
+        let last_line = replacement(2, "fn b( ) {}\n", "fn b() {}\n\n");
+        assert_eq!(
+            "fn a() {}\nfn b() {}\n",
+            apply_to(path, content, vec![&last_line]).unwrap()
+        );
+        // Without replacements of the last line, it is left as it is
+        let first_line = replacement(1, "fn a() {}\n", "fn  a() {}\n");
+        assert_eq!(
+            "fn  a() {}\nfn b( ) {}",
+            apply_to(path, content, vec![&first_line]).unwrap()
+        );
+        // A newline can be added after the last line only
+        let content = "fn a() {}\n";
+        assert_eq!(
+            "fn a() {}\n\n",
+            apply_to(path, content, vec![&replacement(2, "", "\n")]).unwrap()
+        );
+        assert!(apply_to(path, content, vec![&replacement(3, "", "\n")]).is_err());
+    }
+
+    #[test]
+    fn test_apply_to_duplicates() {
+        let content = "fn a( ) {}\n";
+        let r = replacement(1, "fn a( ) {}\n", "fn a() {}\n");
+        assert_eq!(
+            "fn a() {}\n",
+            apply_to(Path::new("lib.rs"), content, vec![&r, &r]).unwrap()
+        );
+        // Overlapping replacements are skipped
+        let other = replacement(1, "fn a( ) {}\n", "fn  a() {}\n");
+        assert_eq!(
+            "fn a() {}\n",
+            apply_to(Path::new("lib.rs"), content, vec![&r, &other]).unwrap()
+        );
+        assert_eq!(
+            "fn a() {}\n",
+            apply_to(Path::new("lib.rs"), content, vec![&r, &other, &r, &other]).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_apply_to_out_of_date() {
+        let path = Path::new("lib.rs");
+        let content = "fn a() {}\n";
+        let changed = replacement(1, "fn a( ) {}\n", "fn a() {}\n");
+        assert!(apply_to(path, content, vec![&changed]).is_err());
+        let removed = replacement(3, "", "\n");
+        assert!(apply_to(path, content, vec![&removed]).is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "fn a( ) {}\nfn b( ) {}\n").unwrap();
+        let lint = |line: u32, replacements: Vec<Replacement>| Lint {
+            message: String::new(),
+            location: Location {
+                path: PathBuf::from("src/lib.rs"),
+                lines: [line, line],
+            },
+            replacements,
+            ..Lint::default()
+        };
+        let lints = vec![
+            lint(2, vec![replacement(2, "fn b( ) {}\n", "fn b() {}\n")]),
+            lint(1, Vec::new()),
+        ];
+        assert_eq!(
+            vec![PathBuf::from("src/lib.rs")],
+            apply(dir.path(), &lints).unwrap()
+        );
+        // Only the lines of the lints with replacements change
+        assert_eq!(
+            "fn a( ) {}\nfn b() {}\n",
+            fs::read_to_string(dir.path().join("src/lib.rs")).unwrap()
+        );
+        // The fixed file doesn't match the replacements anymore
+        assert!(apply(dir.path(), &lints).unwrap().is_empty());
+        assert!(apply(dir.path(), &[lint(1, Vec::new())])
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_fix() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("lib.rs"), "fn a( ) {}\n").unwrap();
+        let lints = vec![Lint {
+            message: String::new(),
+            location: Location {
+                path: PathBuf::from("lib.rs"),
+                lines: [1, 1],
+            },
+            replacements: vec![replacement(1, "fn a( ) {}\n", "fn a() {}\n")],
+            ..Lint::default()
+        }];
+        let fixed_files = fix(dir.path(), &lints).unwrap();
+        assert_eq!(
+            vec![FixedFile {
+                path: PathBuf::from("lib.rs"),
+                original: "fn a( ) {}\n".to_string(),
+                fixed: "fn a() {}\n".to_string(),
+            }],
+            fixed_files
+        );
+        // Files are left untouched
+        assert_eq!(
+            "fn a( ) {}\n",
+            fs::read_to_string(dir.path().join("lib.rs")).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_fix_skips_mismatched_files() {
+        let dir = TempDir::new().unwrap();
+        // Rustc normalizes the text of the spans to LF line endings
+        fs::write(dir.path().join("crlf.rs"), "fn a( ) {}\r\n").unwrap();
+        fs::write(dir.path().join("lf.rs"), "fn a( ) {}\n").unwrap();
+        let lint = |path: &str| Lint {
+            message: String::new(),
+            location: Location {
+                path: PathBuf::from(path),
+                lines: [1, 1],
+            },
+            replacements: vec![replacement(1, "fn a( ) {}\n", "fn a() {}\n")],
+            ..Lint::default()
+        };
+        let lints = vec![lint("crlf.rs"), lint("lf.rs")];
+        assert_eq!(
+            vec![PathBuf::from("lf.rs")],
+            apply(dir.path(), &lints).unwrap()
+        );
+        assert_eq!(
+            "fn a( ) {}\r\n",
+            fs::read_to_string(dir.path().join("crlf.rs")).unwrap()
+        );
+        assert_eq!(
+            "fn a() {}\n",
+            fs::read_to_string(dir.path().join("lf.rs")).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_patch() {
+        let files = vec![FixedFile {
+            path: PathBuf::from("src/lib.rs"),
+            original: "fn a() {}\nfn b( ) {}".to_string(),
+            fixed: "fn a() {}\nfn b() {}\n".to_string(),
+        }];
+        assert_eq!(
+            "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,2 +1,2 @@\n fn a() {}\n-fn b( ) {}\n\\ No newline at end of file\n+fn b() {}\n",
+            patch(&files)
+        );
+        assert!(patch(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_in_sections() {
+        let section = |file_name: &str, line_start: u32, line_end: u32| Section {
+            file_name: PathBuf::from(file_name),
+            line_start,
+            line_end,
+        };
+        let lint = Lint {
+            message: String::new(),
+            location: Location {
+                path: PathBuf::from("src/lib.rs"),
+                lines: [2, 3],
+            },
+            replacements: vec![
+                replacement(2, "a\n", "b\n"),
+                replacement(2, "a\nb\n", "c\n"),
+                replacement(4, "", "\n"),
+            ],
+            ..Lint::default()
+        };
+        let sections = vec![section("src/lib.rs", 2, 3), section("src/main.rs", 1, 10)];
+        let lints = vec![lint];
+        // Line 3 hasn't been changed
+        assert_eq!(
+            vec![replacement(2, "a\n", "b\n")],
+            in_sections(&lints, &sections)[0].replacements
+        );
+        let lints = in_sections(&lints, &[section("./src/lib.rs", 2, 4)]);
+        assert_eq!(3, lints[0].replacements.len());
+    }
+
+    #[test]
+    fn test_in_sections_partial_mismatch() {
+        let content = "fn a() {\nlet x=1;\n  let y = 2;\n}\n";
+        // rustfmt reports the adjacent lines 2 and 3 as a single mismatch
+        let lint = Lint {
+            message: String::new(),
+            location: Location {
+                path: PathBuf::from("src/lib.rs"),
+                lines: [2, 3],
+            },
+            replacements: vec![replacement(
+                2,
+                "let x=1;\n  let y = 2;\n",
+                "    let x = 1;\n    let y = 2;\n",
+            )],
+            ..Lint::default()
+        };
+        // Only line 2 has been changed
+        let sections = [Section {
+            file_name: PathBuf::from("src/lib.rs"),
+            line_start: 2,
+            line_end: 3,
+        }];
+        let lints = in_sections(&[lint], &sections);
+        assert!(lints[0].replacements.is_empty());
+        let replacements = lints[0].replacements.iter().collect();
+        assert_eq!(
+            content,
+            apply_to(Path::new("src/lib.rs"), content, replacements).unwrap()
+        );
+    }
+}
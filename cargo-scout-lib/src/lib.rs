@@ -1,5 +1,7 @@
 pub mod config;
 pub mod error;
+pub mod filter;
+pub mod fix;
 pub mod linter;
 pub mod scout;
 pub mod vcs;
@@ -1,13 +1,16 @@
-use crate::linter;
-use crate::utils::get_absolute_file_path;
-use cargo_scout_macros::{error, info};
+use crate::config::file::MemberConfig;
+use crate::config::Member;
+use crate::linter::toolchain::{command_error, output, Toolchain};
+use crate::linter::{self, Linter};
+use crate::utils::{parallel_map, paths_match};
+use cargo_scout_macros::{error, info, warn};
 use colored::Colorize;
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
-use std::process::Command;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 #[allow(clippy::struct_excessive_bools)]
 pub struct Clippy {
     verbose: bool,
@@ -15,6 +18,15 @@ pub struct Clippy {
     all_features: bool,
     features: Option<String>,
     preview: bool,
+    // Target flags, such as `--lib`
+    targets: Vec<String>,
+    lint_groups: Option<Vec<String>>,
+    members: BTreeMap<String, MemberConfig>,
+    workspace_run: bool,
+    jobs: usize,
+    isolated_target_dir: Option<PathBuf>,
+    target_dir: Option<PathBuf>,
+    toolchain: Toolchain,
 }
 
 #[derive(Deserialize, Clone)]
@@ -24,6 +36,8 @@ struct Lint {
     /// Example:
     /// "cargo-scout-lib".to_string()
     package_id: String,
+    /// The `Cargo.toml` of the package the lint was reported on
+    manifest_path: Option<PathBuf>,
     /// The file the lint was reported on
     /// Example:
     /// Some("src/lib.rs".to_string())
@@ -40,17 +54,66 @@ struct Message {
     /// Example:
     /// unused variable `count`
     rendered: String,
+    /// The level of the message
+    /// Example:
+    /// "error".to_string()
+    #[serde(default)]
+    level: String,
+    /// The error code or the lint name
+    /// Example:
+    /// Some(Code { code: "E0308".to_string() })
+    #[serde(default)]
+    code: Option<Code>,
     /// The file names and lines the lint
     /// was reported on
     spans: Vec<Span>,
+    /// The help messages and their suggestions
+    #[serde(default)]
+    children: Vec<Child>,
+}
+
+#[derive(Deserialize, Clone)]
+/// The code of a message, such as `E0308` or `clippy::len_zero`
+struct Code {
+    code: String,
+}
+
+#[derive(Deserialize, Clone)]
+/// A child message, such as `help: try this`
+struct Child {
+    /// The spans of a suggestion, to be applied together
+    spans: Vec<Span>,
 }
 
 #[derive(Deserialize, Clone)]
 /// A `Span` has a file name, a start and an end line
+///
+/// Spans of suggestions also have columns, a replacement
+/// and the text of the lines they cover.
 struct Span {
     file_name: String,
     line_start: u32,
     line_end: u32,
+    /// The first column, starting at 1 and counted in characters
+    #[serde(default)]
+    column_start: usize,
+    /// The column after the last one
+    #[serde(default)]
+    column_end: usize,
+    #[serde(default)]
+    suggested_replacement: Option<String>,
+    /// Example:
+    /// Some("MachineApplicable".to_string())
+    #[serde(default)]
+    suggestion_applicability: Option<String>,
+    /// The lines of the span, from `line_start` to `line_end`
+    #[serde(default)]
+    text: Vec<SpanLine>,
+}
+
+#[derive(Deserialize, Clone)]
+struct SpanLine {
+    text: String,
 }
 
 impl linter::Linter for Clippy {
@@ -63,9 +126,81 @@ impl linter::Linter for Clippy {
             "[Clippy] - getting lints for directory {}",
             &working_dir.to_str().unwrap_or("<no directory>")
         );
-        self.clippy(working_dir)
+        self.clippy(working_dir, &[])
             .map(|clippy_output| lints(clippy_output.as_ref()))
     }
+
+    fn lints_for(&self, member: &Member) -> Result<Vec<linter::Lint>, crate::error::Error> {
+        match self.members.get(&member.name) {
+            Some(member_config) => {
+                info!("[Clippy] - using the settings of member {}", &member.name);
+                self.with_member_config(member_config)
+                    .lints(member.root.clone())
+            }
+            None => self.lints(member.root.clone()),
+        }
+    }
+
+    fn lints_for_members(
+        &self,
+        root: &Path,
+        members: &[Member],
+    ) -> Result<Vec<linter::Lint>, crate::error::Error> {
+        let (own_run, shared_run): (Vec<&Member>, Vec<&Member>) =
+            members.iter().partition(|m| self.has_own_run(m));
+        let mut lints = Vec::new();
+        if !shared_run.is_empty() {
+            lints.extend(self.shared_run(root, &shared_run)?);
+        }
+        for result in self.own_runs(&own_run, false) {
+            lints.extend(result?);
+        }
+        Ok(lints)
+    }
+
+    fn lints_for_files_keep_going(
+        &self,
+        root: &Path,
+        members: &[Member],
+        _files: &[Vec<PathBuf>],
+    ) -> (Vec<linter::Lint>, Vec<(usize, crate::error::Error)>) {
+        let (mut own_run, shared_run): (Vec<usize>, Vec<usize>) =
+            (0..members.len()).partition(|&i| self.has_own_run(&members[i]));
+        let members_at =
+            |indexes: &[usize]| -> Vec<&Member> { indexes.iter().map(|&i| &members[i]).collect() };
+        let mut lints = Vec::new();
+        let mut failures = Vec::new();
+        if !shared_run.is_empty() {
+            match self.shared_run(root, &members_at(&shared_run)) {
+                Ok(shared_lints) => lints.extend(shared_lints),
+                Err(e) if shared_run.len() == 1 => failures.push((shared_run[0], e)),
+                // The failure can't be attributed to one of the members linted together,
+                // so they get their own run
+                Err(_) => {
+                    info!(
+                        "[Clippy] - linting failed, linting the {} packages one by one",
+                        shared_run.len()
+                    );
+                    own_run.extend(shared_run);
+                }
+            }
+        }
+        let results = self.own_runs(&members_at(&own_run), true);
+        for (i, result) in own_run.into_iter().zip(results) {
+            match result {
+                Ok(member_lints) => lints.extend(member_lints),
+                Err(e) => failures.push((i, e)),
+            }
+        }
+        failures.sort_by_key(|(i, _)| *i);
+        (lints, failures)
+    }
+}
+
+// Concurrent cargo runs wait on each other's target directory lock,
+// so each member linted at the same time gets its own target directory.
+fn member_target_dir(isolated_target_dir: &Path, member: &Member) -> PathBuf {
+    isolated_target_dir.join(&member.name)
 }
 
 impl Clippy {
@@ -94,10 +229,160 @@ impl Clippy {
         self
     }
 
+    /// The toolchain clippy runs with.
+    ///
+    /// Preview mode falls back to nightly when nothing selects a toolchain.
+    pub fn set_toolchain(&mut self, toolchain: Toolchain) -> &mut Self {
+        self.toolchain = toolchain;
+        self
+    }
+
+    fn toolchain(&self) -> Toolchain {
+        let mut toolchain = self.toolchain.clone();
+        if self.preview {
+            toolchain.set_fallback(Some("nightly".to_string()));
+        }
+        toolchain
+    }
+
+    /// In workspace run mode, clippy runs once for all the members to lint,
+    /// (except the ones with their own settings), instead of once per member.
+    pub fn set_workspace_run(&mut self, workspace_run: bool) -> &mut Self {
+        self.workspace_run = workspace_run;
+        self
+    }
+
+    /// Only check these targets, named like cargo flags without dashes:
+    /// `lib`, `bins`, `examples`, `tests`, `benches` or `all-targets`.
+    ///
+    /// All the targets are checked if none are set.
+    pub fn set_targets(&mut self, targets: &[String]) -> &mut Self {
+        self.targets = targets.iter().map(|t| format!("--{}", t)).collect();
+        self
+    }
+
+    /// Up to `jobs` members are linted at once.
+    ///
+    /// The members share the target directory of the workspace, so cargo would build them
+    /// one after the other in separate runs: the ones without their own settings
+    /// are linted in a single run instead, where cargo builds them in parallel.
+    /// With isolated target directories, each member gets its own run and target directory.
+    ///
+    /// Setting it to 0 or 1 lints them one after the other.
+    pub fn set_jobs(&mut self, jobs: usize) -> &mut Self {
+        self.jobs = jobs;
+        self
+    }
+
+    /// The directory the members linted at the same time get their own target directory in,
+    /// so the jobs don't wait on each other, at the cost of building the dependencies of each member.
+    pub fn set_isolated_target_dir(&mut self, isolated_target_dir: Option<PathBuf>) -> &mut Self {
+        self.isolated_target_dir = isolated_target_dir;
+        self
+    }
+
+    // Members with their own settings need their own run
+    fn has_own_run(&self, member: &Member) -> bool {
+        let shared_run =
+            self.workspace_run || (self.jobs > 1 && self.isolated_target_dir.is_none());
+        !shared_run || self.members.contains_key(&member.name)
+    }
+
+    fn shared_run(
+        &self,
+        root: &Path,
+        members: &[&Member],
+    ) -> Result<Vec<linter::Lint>, crate::error::Error> {
+        let packages: Vec<String> = members.iter().map(|m| m.name.clone()).collect();
+        info!(
+            "[Clippy] - getting lints for packages {}",
+            packages.join(", ")
+        );
+        let manifest_paths: Vec<&Path> =
+            members.iter().map(|m| m.manifest_path.as_path()).collect();
+        let clippy_output = self.clippy(root, &packages)?;
+        Ok(lints_of_members(&clippy_output, &manifest_paths))
+    }
+
+    // Results are gathered in the members order,
+    // so the output and the first error reported don't depend on scheduling.
+    // Unless `keep_going` is set, members linted one after the other stop at the first failure.
+    fn own_runs(
+        &self,
+        members: &[&Member],
+        keep_going: bool,
+    ) -> Vec<Result<Vec<linter::Lint>, crate::error::Error>> {
+        if let Some(isolated_target_dir) = &self.isolated_target_dir {
+            if self.jobs > 1 && members.len() > 1 {
+                info!(
+                    "[Clippy] - linting {} members with {} jobs",
+                    members.len(),
+                    self.jobs
+                );
+                return parallel_map(self.jobs, members, |m| {
+                    self.with_target_dir(member_target_dir(isolated_target_dir, m))
+                        .lints_for(m)
+                });
+            }
+        }
+        let mut results = Vec::new();
+        for m in members {
+            let result = self.lints_for(m);
+            let failed = result.is_err();
+            results.push(result);
+            if failed && !keep_going {
+                break;
+            }
+        }
+        results
+    }
+
+    fn with_target_dir(&self, target_dir: PathBuf) -> Self {
+        let mut clippy = self.clone();
+        clippy.target_dir = Some(target_dir);
+        clippy
+    }
+
+    /// Members with settings will be linted with them,
+    /// instead of the ones set on the `Clippy` instance.
+    pub fn set_member_configs(&mut self, members: BTreeMap<String, MemberConfig>) -> &mut Self {
+        self.members = members;
+        self
+    }
+
+    fn with_member_config(&self, member_config: &MemberConfig) -> Self {
+        let mut clippy = self.clone();
+        if let Some(features) = &member_config.features {
+            clippy.features = if features.is_empty() {
+                None
+            } else {
+                Some(features.join(","))
+            };
+        }
+        if let Some(no_default_features) = member_config.no_default_features {
+            clippy.no_default_features = no_default_features;
+        }
+        if let Some(all_features) = member_config.all_features {
+            clippy.all_features = all_features;
+        }
+        if let Some(targets) = &member_config.targets {
+            clippy.set_targets(targets);
+        }
+        if let Some(lints) = &member_config.lints {
+            clippy.lint_groups = Some(lints.clone());
+        }
+        clippy
+    }
+
+    #[cfg(test)]
     fn command_parameters(&self) -> Vec<&str> {
+        self.command_parameters_for(&[])
+    }
+
+    // `packages` are passed with `-p`
+    fn command_parameters_for<'a>(&'a self, packages: &'a [String]) -> Vec<&'a str> {
         let mut params = if self.preview {
             vec![
-                "+nightly",
                 "clippy-preview",
                 "-Z",
                 "unstable-options",
@@ -119,14 +404,23 @@ impl Clippy {
         if let Some(features) = &self.features {
             params.append(&mut vec!["--features", features]);
         }
-        params.append(&mut vec![
-            "--tests",
-            "--",
-            "-W",
-            "clippy::all",
-            "-W",
-            "clippy::pedantic",
-        ]);
+        for package in packages {
+            params.append(&mut vec!["-p", package]);
+        }
+        if self.targets.is_empty() {
+            params.push("--all-targets");
+        } else {
+            params.extend(self.targets.iter().map(String::as_str));
+        }
+        params.push("--");
+        match &self.lint_groups {
+            Some(lint_groups) => {
+                for lint_group in lint_groups {
+                    params.append(&mut vec!["-W", lint_group]);
+                }
+            }
+            None => params.append(&mut vec!["-W", "clippy::all", "-W", "clippy::pedantic"]),
+        }
         params
     }
 
@@ -142,13 +436,29 @@ impl Clippy {
     // because an external command
     // cannot be easily unit tested
     #[cfg(not(tarpaulin_include))]
-    fn clippy(&self, path: impl AsRef<Path>) -> Result<String, crate::error::Error> {
-        let clippy_pedantic_output = Command::new("cargo")
-            .current_dir(path)
-            .args(self.command_parameters())
-            .envs(self.envs())
-            .output()
-            .expect("failed to run clippy pedantic");
+    fn clippy(
+        &self,
+        path: impl AsRef<Path>,
+        packages: &[String],
+    ) -> Result<String, crate::error::Error> {
+        let toolchain = self.toolchain().select(&path);
+        info!(
+            "[Clippy] - using {}{}",
+            toolchain,
+            toolchain
+                .version(&path)
+                .map(|v| format!(": {}", v))
+                .unwrap_or_default()
+        );
+        let mut command = toolchain.cargo();
+        command
+            .current_dir(&path)
+            .args(self.command_parameters_for(packages))
+            .envs(self.envs());
+        if let Some(target_dir) = &self.target_dir {
+            command.env("CARGO_TARGET_DIR", target_dir);
+        }
+        let clippy_pedantic_output = output(&mut command)?;
 
         if self.verbose {
             info!(
@@ -158,31 +468,32 @@ impl Clippy {
         }
         if clippy_pedantic_output.status.success() {
             Ok(String::from_utf8(clippy_pedantic_output.stdout)?)
+        } else if has_errors(&String::from_utf8_lossy(&clippy_pedantic_output.stdout)) {
+            // The errors, and the lints denied in the code, are reported like lints,
+            // instead of the whole output of the failed run
+            let stdout = String::from_utf8(clippy_pedantic_output.stdout)?;
+            if lints(&stdout)
+                .iter()
+                .any(|l| l.level == linter::Level::Error)
+            {
+                warn!("[Clippy] - the code doesn't compile, reporting its errors");
+            }
+            Ok(stdout)
         } else if self.verbose {
             error!("Clippy run failed");
             info!("cleaning and building with full backtrace");
-            let _ = Command::new("cargo")
-                .args(&["clean"])
-                .envs(self.envs())
-                .output()
-                .expect("failed to start cargo clean");
-            let build = Command::new("cargo")
-                .args(&["build"])
-                .envs(self.envs())
-                .output()
-                .expect("failed to start cargo build");
+            let _ = output(toolchain.cargo().args(["clean"]).envs(self.envs()))?;
+            let build = output(toolchain.cargo().args(["build"]).envs(self.envs()))?;
             if build.status.success() {
                 Err(crate::error::Error::Command(String::from_utf8(
                     build.stdout,
                 )?))
             } else {
                 io::stdout().write_all(&build.stdout)?;
-                Err(crate::error::Error::Command(String::from_utf8(
-                    build.stderr,
-                )?))
+                Err(command_error(String::from_utf8(build.stderr)?))
             }
         } else {
-            Err(crate::error::Error::Command(String::from_utf8(
+            Err(command_error(String::from_utf8(
                 clippy_pedantic_output.stderr,
             )?))
         }
@@ -191,41 +502,181 @@ impl Clippy {
 
 #[must_use]
 fn lints(clippy_output: &str) -> Vec<linter::Lint> {
+    lints_matching(clippy_output, |_| true)
+}
+
+// Errors come with spans, unlike e.g. a missing clippy component
+fn has_errors(clippy_output: &str) -> bool {
+    messages(clippy_output, |_| true)
+        .iter()
+        .any(|m| m.level.starts_with("error"))
+}
+
+// Other workspace members may be checked as dependencies,
+// their lints are attributed to them through their manifest path.
+#[must_use]
+fn lints_of_members(clippy_output: &str, manifest_paths: &[&Path]) -> Vec<linter::Lint> {
+    lints_matching(clippy_output, |lint| {
+        lint.manifest_path.as_ref().map_or(true, |path| {
+            manifest_paths.iter().any(|m| paths_match(m, path))
+        })
+    })
+}
+
+fn lints_matching(clippy_output: &str, keep: impl Fn(&Lint) -> bool) -> Vec<linter::Lint> {
     let mut lints = Vec::new();
 
-    let clippy_messages: Vec<Message> = clippy_output
+    // Span paths are relative to the workspace root
+    for c in messages(clippy_output, keep) {
+        for s in &c.spans {
+            lints.push(linter::Lint {
+                message: c.rendered.clone(),
+                location: linter::Location {
+                    path: PathBuf::from(&s.file_name),
+                    lines: [s.line_start, s.line_end],
+                },
+                level: level(&c),
+                replacements: replacements(&c.children, &s.file_name),
+            });
+        }
+    }
+    lints
+}
+
+// The messages with spans
+fn messages(clippy_output: &str, keep: impl Fn(&Lint) -> bool) -> Vec<Message> {
+    clippy_output
         .lines()
         .filter(|l| l.starts_with('{'))
         .filter_map(|line| {
             if let Ok(lint) = serde_json::from_str::<Lint>(line) {
-                lint.message
+                if keep(&lint) {
+                    lint.message
+                } else {
+                    None
+                }
             } else {
                 None
             }
         })
         .filter(|message: &Message| !message.spans.is_empty())
-        .collect();
-
-    for c in clippy_messages {
-        for s in c.spans {
-            if let Ok(path) = get_absolute_file_path(s.file_name) {
-                lints.push(linter::Lint {
-                    message: c.rendered.clone(),
-                    location: linter::Location {
-                        path,
-                        lines: [s.line_start, s.line_end],
-                    },
-                })
+        .collect()
+}
+
+// Lints denied in the code are errors too, but the code compiles:
+// compilation errors have no code, or an error code such as `E0308`.
+// Internal compiler errors are reported as `error: internal compiler error`
+fn level(message: &Message) -> linter::Level {
+    let compilation_error = message.level.starts_with("error")
+        && message
+            .code
+            .as_ref()
+            .map_or(true, |c| is_error_code(&c.code));
+    if compilation_error {
+        linter::Level::Error
+    } else {
+        linter::Level::Warning
+    }
+}
+
+fn is_error_code(code: &str) -> bool {
+    code.len() == 5 && code.starts_with('E') && code[1..].chars().all(|c| c.is_ascii_digit())
+}
+
+// Only machine applicable suggestions are kept, and only if all their spans
+// are in `file_name`: a suggestion can't be applied partially.
+fn replacements(children: &[Child], file_name: &str) -> Vec<linter::Replacement> {
+    children
+        .iter()
+        .filter(|c| c.spans.iter().any(|s| s.suggested_replacement.is_some()))
+        .filter(|c| {
+            c.spans.iter().all(|s| {
+                s.file_name == file_name
+                    && s.suggested_replacement.is_some()
+                    && s.suggestion_applicability.as_deref() == Some("MachineApplicable")
+            })
+        })
+        .filter_map(|c| suggestion_replacements(&c.spans))
+        .flatten()
+        .collect()
+}
+
+// Replacements are made of whole lines,
+// so spans sharing lines are merged into one replacement.
+fn suggestion_replacements(spans: &[Span]) -> Option<Vec<linter::Replacement>> {
+    let mut spans: Vec<&Span> = spans.iter().collect();
+    spans.sort_by_key(|s| (s.line_start, s.column_start));
+    let mut groups: Vec<Vec<&Span>> = Vec::new();
+    for span in spans {
+        match groups.last_mut() {
+            Some(group) if group.iter().any(|s| s.line_end >= span.line_start) => {
+                group.push(span);
             }
+            _ => groups.push(vec![span]),
         }
     }
-    lints
+    groups.iter().map(|group| merge_spans(group)).collect()
+}
+
+fn merge_spans(spans: &[&Span]) -> Option<linter::Replacement> {
+    let first_line = spans.first()?.line_start;
+    let last_line = spans.iter().map(|s| s.line_end).max()?;
+    let mut lines: BTreeMap<u32, &str> = BTreeMap::new();
+    for span in spans {
+        for (line, text) in (span.line_start..).zip(&span.text) {
+            lines.insert(line, &text.text);
+        }
+    }
+    if lines.len() != (last_line - first_line + 1) as usize {
+        return None;
+    }
+    let mut line_offsets = Vec::new();
+    let mut original = String::new();
+    for text in lines.values() {
+        line_offsets.push(original.len());
+        original.push_str(text);
+        original.push('\n');
+    }
+    let offset = |line: u32, column: usize| {
+        let start = *line_offsets.get((line - first_line) as usize)?;
+        let text = lines.get(&line)?;
+        let index = text
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(text.len()))
+            .nth(column.checked_sub(1)?)?;
+        Some(start + index)
+    };
+    let mut replacement = String::new();
+    let mut replaced_until = 0;
+    for span in spans {
+        let start = offset(span.line_start, span.column_start)?;
+        let end = offset(span.line_end, span.column_end)?;
+        // Overlapping spans can't be applied together
+        if start < replaced_until || end < start {
+            return None;
+        }
+        replacement.push_str(&original[replaced_until..start]);
+        replacement.push_str(span.suggested_replacement.as_deref()?);
+        replaced_until = end;
+    }
+    replacement.push_str(&original[replaced_until..]);
+    Some(linter::Replacement {
+        line: first_line,
+        original,
+        replacement,
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{get_absolute_file_path, lints, Clippy};
+    use super::{
+        has_errors, lints, lints_of_members, member_target_dir, Clippy, Member, MemberConfig, Path,
+        PathBuf, Toolchain,
+    };
     use crate::error::Error;
+    use crate::linter::{Level, Replacement};
+    use std::collections::BTreeMap;
 
     #[test]
     fn test_set_verbose() {
@@ -257,7 +708,7 @@ mod tests {
             "clippy",
             "--message-format",
             "json",
-            "--tests",
+            "--all-targets",
             "--",
             "-W",
             "clippy::all",
@@ -293,6 +744,139 @@ mod tests {
             .contains(&"foo bar baz"));
     }
 
+    #[test]
+    fn test_member_config() {
+        let mut linter = Clippy::default();
+        linter.set_features(Some(String::from("foo")));
+        let member_config = MemberConfig {
+            no_default_features: Some(true),
+            features: Some(vec!["alloc".to_string(), "bar".to_string()]),
+            targets: Some(vec!["lib".to_string(), "tests".to_string()]),
+            lints: Some(vec!["clippy::all".to_string()]),
+            ..MemberConfig::default()
+        };
+        let member_linter = linter.with_member_config(&member_config);
+        let expected_command_parameters = vec![
+            "clippy",
+            "--message-format",
+            "json",
+            "--no-default-features",
+            "--features",
+            "alloc,bar",
+            "--lib",
+            "--tests",
+            "--",
+            "-W",
+            "clippy::all",
+        ];
+        assert_eq!(
+            expected_command_parameters,
+            member_linter.command_parameters()
+        );
+
+        // Settings that are not overridden are kept
+        let member_linter = linter.with_member_config(&MemberConfig::default());
+        assert_eq!(
+            linter.command_parameters(),
+            member_linter.command_parameters()
+        );
+    }
+
+    #[test]
+    fn test_set_targets() {
+        let mut linter = Clippy::default();
+        assert!(linter.command_parameters().contains(&"--all-targets"));
+
+        let targets_linter = linter.set_targets(&["lib".to_string(), "examples".to_string()]);
+        let params = targets_linter.command_parameters();
+        assert!(params.contains(&"--lib"));
+        assert!(params.contains(&"--examples"));
+        assert!(!params.contains(&"--all-targets"));
+
+        let all_targets_linter = linter.set_targets(&[]);
+        assert!(all_targets_linter
+            .command_parameters()
+            .contains(&"--all-targets"));
+    }
+
+    #[test]
+    fn test_get_command_parameters_for_packages() {
+        let linter = Clippy::default();
+        let packages = vec!["foo".to_string(), "bar".to_string()];
+        let expected_command_parameters = vec![
+            "clippy",
+            "--message-format",
+            "json",
+            "-p",
+            "foo",
+            "-p",
+            "bar",
+            "--all-targets",
+            "--",
+            "-W",
+            "clippy::all",
+            "-W",
+            "clippy::pedantic",
+        ];
+        assert_eq!(
+            expected_command_parameters,
+            linter.command_parameters_for(&packages)
+        );
+    }
+
+    #[test]
+    fn test_member_target_dir() {
+        let mut member = Member::from_dir("/p", "crates/foo");
+        member.name = "foo".to_string();
+        let target_dir = member_target_dir(Path::new("/p/target/scout"), &member);
+        assert_eq!(Path::new("/p/target/scout/foo"), target_dir);
+        // Every member gets its own directory
+        member.name = "bar".to_string();
+        assert_ne!(
+            target_dir,
+            member_target_dir(Path::new("/p/target/scout"), &member)
+        );
+    }
+
+    #[test]
+    fn test_has_own_run() {
+        let mut member = Member::from_dir("/p", "crates/foo");
+        member.name = "foo".to_string();
+        let mut linter = Clippy::default();
+        assert!(linter.has_own_run(&member));
+        // Jobs sharing the target directory lint the members in a single run
+        linter.set_jobs(4);
+        assert!(!linter.has_own_run(&member));
+        linter.set_isolated_target_dir(Some(PathBuf::from("/p/target/scout")));
+        assert!(linter.has_own_run(&member));
+        linter.set_jobs(1).set_workspace_run(true);
+        assert!(!linter.has_own_run(&member));
+        // Unless they have their own settings
+        let mut members = BTreeMap::new();
+        members.insert("foo".to_string(), MemberConfig::default());
+        linter.set_member_configs(members);
+        assert!(linter.has_own_run(&member));
+    }
+
+    #[test]
+    fn test_toolchain() {
+        let mut linter = Clippy::default();
+        assert_eq!(Toolchain::default(), linter.toolchain());
+
+        // Preview mode needs nightly, unless another toolchain is selected
+        linter.set_preview(true);
+        let mut nightly = Toolchain::default();
+        nightly.set_fallback(Some("nightly".to_string()));
+        assert_eq!(nightly, linter.toolchain());
+        assert!(!linter.command_parameters().contains(&"+nightly"));
+
+        let mut pinned = Toolchain::default();
+        pinned.set_name(Some("nightly-2020-01-01".to_string()));
+        linter.set_toolchain(pinned.clone());
+        pinned.set_fallback(Some("nightly".to_string()));
+        assert_eq!(pinned, linter.toolchain());
+    }
+
     #[test]
     fn test_get_command_parameters_nightly() {
         let mut nightly_linter = Clippy::default();
@@ -336,9 +920,10 @@ mod tests {
         let expected_lints = vec![linter::Lint {
             message: "this is a test lint".to_string(),
             location: linter::Location {
-                path: get_absolute_file_path("test/foo/baz.rs")?,
+                path: PathBuf::from("test/foo/baz.rs"),
                 lines: [10, 12],
             },
+            ..linter::Lint::default()
         }];
 
         let clippy_output = r#"{"package_id": "cargo-scout","src_path": "test/foo/bar.rs","message": { "rendered": "this is a test lint","spans": [{"file_name": "test/foo/baz.rs","line_start": 10,"line_end": 12}]}}"#;
@@ -346,4 +931,100 @@ mod tests {
         assert_eq!(expected_lints, lints(clippy_output));
         Ok(())
     }
+
+    #[test]
+    fn test_errors() {
+        let clippy_output = [
+            r#"{"package_id": "foo","message": { "rendered": "mismatched types","level": "error","spans": [{"file_name": "src/lib.rs","line_start": 2,"line_end": 2}]}}"#,
+            r#"{"package_id": "foo","message": { "rendered": "For more information about this error","level": "failure-note","spans": []}}"#,
+            r#"{"reason": "build-finished","success": false}"#,
+        ]
+        .join("\n");
+        let lints = lints(&clippy_output);
+        assert_eq!(1, lints.len());
+        assert_eq!(Level::Error, lints[0].level);
+        assert!(has_errors(&clippy_output));
+
+        let warning = r#"{"package_id": "foo","message": { "rendered": "unused variable","level": "warning","spans": [{"file_name": "src/lib.rs","line_start": 2,"line_end": 2}]}}"#;
+        assert_eq!(Level::Warning, super::lints(warning)[0].level);
+        assert!(!has_errors(warning));
+        assert!(!has_errors("error: no such command: `clippy`"));
+
+        // Lints denied in the code fail the run, but aren't compilation errors
+        let denied = r#"{"package_id": "foo","message": { "rendered": "length comparison to zero","level": "error","code": {"code": "clippy::len_zero","explanation": null},"spans": [{"file_name": "src/lib.rs","line_start": 2,"line_end": 2}]}}"#;
+        assert_eq!(Level::Warning, super::lints(denied)[0].level);
+        assert!(has_errors(denied));
+        let coded = r#"{"package_id": "foo","message": { "rendered": "mismatched types","level": "error","code": {"code": "E0308","explanation": "..."},"spans": [{"file_name": "src/lib.rs","line_start": 2,"line_end": 2}]}}"#;
+        assert_eq!(Level::Error, super::lints(coded)[0].level);
+    }
+
+    #[test]
+    fn test_lints_of_members() {
+        let clippy_output = [
+            r#"{"package_id": "foo","manifest_path": "/p/foo/Cargo.toml","message": { "rendered": "foo lint","spans": [{"file_name": "foo/src/lib.rs","line_start": 1,"line_end": 1}]}}"#,
+            r#"{"package_id": "bar","manifest_path": "/p/bar/Cargo.toml","message": { "rendered": "bar lint","spans": [{"file_name": "bar/src/lib.rs","line_start": 1,"line_end": 1}]}}"#,
+            r#"{"package_id": "baz","message": { "rendered": "baz lint","spans": [{"file_name": "baz/src/lib.rs","line_start": 1,"line_end": 1}]}}"#,
+        ]
+        .join("\n");
+
+        let actual_lints = lints_of_members(&clippy_output, &[Path::new("/p/foo/Cargo.toml")]);
+        let messages: Vec<&str> = actual_lints.iter().map(|l| l.message.as_str()).collect();
+        // `bar` is only checked as a dependency,
+        // and lints without a manifest path are kept.
+        assert_eq!(vec!["foo lint", "baz lint"], messages);
+    }
+
+    #[test]
+    fn test_lints_with_suggestions() {
+        let span = |start: usize, end: usize, replacement: &str, applicability: &str| {
+            format!(
+                r#"{{"file_name": "src/lib.rs","line_start": 2,"line_end": 2,"column_start": {},"column_end": {},"suggested_replacement": "{}","suggestion_applicability": "{}","text": [{{"text": "    return x.len() == 0;"}}]}}"#,
+                start, end, replacement, applicability
+            )
+        };
+        let lint = |children: &[String]| {
+            format!(
+                r#"{{"package_id": "foo","message": {{ "rendered": "lint","spans": [{{"file_name": "src/lib.rs","line_start": 2,"line_end": 2}}],"children": [{}]}}}}"#,
+                children.join(",")
+            )
+        };
+        let needless_return = format!(
+            r#"{{"spans": [{}, {}]}}"#,
+            span(5, 24, "x.len() == 0", "MachineApplicable"),
+            span(24, 25, "", "MachineApplicable")
+        );
+        let help = r#"{"spans": []}"#.to_string();
+        let lints = lints(&lint(&[help, needless_return]));
+        // The spans of a suggestion become one replacement of the whole line
+        assert_eq!(
+            vec![Replacement {
+                line: 2,
+                original: "    return x.len() == 0;\n".to_string(),
+                replacement: "    x.len() == 0\n".to_string(),
+            }],
+            lints[0].replacements
+        );
+
+        let maybe_incorrect = format!(
+            r#"{{"spans": [{}]}}"#,
+            span(12, 24, "x.is_empty()", "MaybeIncorrect")
+        );
+        assert!(super::lints(&lint(&[maybe_incorrect]))[0]
+            .replacements
+            .is_empty());
+    }
+
+    #[test]
+    fn test_multiline_suggestion() {
+        let lint = r#"{"package_id": "foo","message": { "rendered": "lint","spans": [{"file_name": "src/lib.rs","line_start": 1,"line_end": 2}],"children": [{"spans": [{"file_name": "src/lib.rs","line_start": 1,"line_end": 2,"column_start": 9,"column_end": 8,"suggested_replacement": "é","suggestion_applicability": "MachineApplicable","text": [{"text": "let é = (1"}, {"text": "    + 2);"}]}]}]}}"#;
+        // Columns are counted in characters
+        assert_eq!(
+            vec![Replacement {
+                line: 1,
+                original: "let é = (1\n    + 2);\n".to_string(),
+                replacement: "let é = é);\n".to_string(),
+            }],
+            lints(lint)[0].replacements
+        );
+    }
 }
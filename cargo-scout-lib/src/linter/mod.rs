@@ -1,16 +1,105 @@
-use std::path::PathBuf;
+use crate::config::Member;
+use cargo_scout_macros::info;
+use colored::Colorize;
+use std::path::{Path, PathBuf};
 
 pub mod clippy;
 pub mod rustfmt;
+pub mod toolchain;
 
 pub trait Linter {
     #[allow(clippy::missing_errors_doc)]
     fn lints(&self, working_dir: impl Into<PathBuf>) -> Result<Vec<Lint>, crate::error::Error>;
+
+    /// This function should return the lints of a workspace member.
+    ///
+    /// The member root is an absolute path.
+    ///
+    /// It defaults to `lints` in the member directory.
+    #[allow(clippy::missing_errors_doc)]
+    fn lints_for(&self, member: &Member) -> Result<Vec<Lint>, crate::error::Error> {
+        self.lints(member.root.clone())
+    }
+
+    /// This function should return the lints of several workspace members,
+    /// `root` being the absolute path of the workspace root.
+    ///
+    /// Linters able to check several members at once should implement it.
+    ///
+    /// It defaults to `lints_for` on each member.
+    #[allow(clippy::missing_errors_doc)]
+    fn lints_for_members(
+        &self,
+        _root: &Path,
+        members: &[Member],
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        let mut lints = Vec::new();
+        for member in members {
+            lints.extend(self.lints_for(member)?);
+        }
+        Ok(lints)
+    }
+
+    /// This function should return the lints of several workspace members,
+    /// `files` being the absolute paths of the changed files of each member.
+    ///
+    /// Linters able to check files on their own should implement it.
+    ///
+    /// It defaults to `lints_for_members`.
+    #[allow(clippy::missing_errors_doc)]
+    fn lints_for_files(
+        &self,
+        root: &Path,
+        members: &[Member],
+        _files: &[Vec<PathBuf>],
+    ) -> Result<Vec<Lint>, crate::error::Error> {
+        self.lints_for_members(root, members)
+    }
+
+    /// This function should return the lints of the members that could be linted,
+    /// like `lints_for_files`, along with the index and the error of each member that couldn't.
+    ///
+    /// Linters checking several members at once should implement it,
+    /// so a failure doesn't make them lint the other members again.
+    ///
+    /// It defaults to `lints_for_files`, then to `lints_for_files` on each member if it fails.
+    fn lints_for_files_keep_going(
+        &self,
+        root: &Path,
+        members: &[Member],
+        files: &[Vec<PathBuf>],
+    ) -> (Vec<Lint>, Vec<(usize, crate::error::Error)>) {
+        let error = match self.lints_for_files(root, members, files) {
+            Ok(lints) => return (lints, Vec::new()),
+            Err(e) => e,
+        };
+        if members.len() == 1 {
+            return (Vec::new(), vec![(0, error)]);
+        }
+        info!(
+            "[Scout] - linting failed, linting the {} members one by one",
+            members.len()
+        );
+        let mut lints = Vec::new();
+        let mut failures = Vec::new();
+        for (i, (member, member_files)) in members.iter().zip(files).enumerate() {
+            let result = self.lints_for_files(
+                root,
+                std::slice::from_ref(member),
+                std::slice::from_ref(member_files),
+            );
+            match result {
+                Ok(member_lints) => lints.extend(member_lints),
+                Err(e) => failures.push((i, e)),
+            }
+        }
+        (lints, failures)
+    }
 }
 
 /// This struct contains the lint,
 /// It may contain a message, and a location.
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Clone, Debug, Default)]
 pub struct Lint {
     /// The message string
     /// Example:
@@ -19,11 +108,39 @@ pub struct Lint {
     /// The file names and lines the lint
     /// was reported on
     pub location: Location,
+    /// Whether the lint is a warning, or an error preventing compilation
+    pub level: Level,
+    /// The edits fixing the lint, if the linter suggests some
+    pub replacements: Vec<Replacement>,
+}
+
+/// The level of a lint.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Default)]
+pub enum Level {
+    #[default]
+    Warning,
+    /// The code doesn't compile
+    Error,
 }
 
 /// A `Location` has a file name, a start and an end line
-#[derive(PartialEq, Clone, Debug)]
+///
+/// Linters report paths that are either absolute, or relative to the project root.
+/// The lints returned by `Scout::run` have normalized paths, relative to the repository root.
+#[derive(PartialEq, Clone, Debug, Default)]
 pub struct Location {
-    pub path: String,
+    pub path: PathBuf,
     pub lines: [u32; 2],
 }
+
+/// A `Replacement` is an edit of the file a lint was reported on:
+/// the `original` text, made of whole lines starting at line `line`,
+/// is replaced with the `replacement` text.
+///
+/// An empty `original` text inserts the `replacement` text before line `line`.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Replacement {
+    pub line: u32,
+    pub original: String,
+    pub replacement: String,
+}
@@ -1,14 +1,30 @@
+use crate::config::Member;
 use crate::error::Error;
-use crate::linter::{Lint, Linter, Location};
-use crate::utils::get_absolute_file_path;
-use cargo_scout_macros::info;
+use crate::linter::toolchain::{command_error, is_nightly, output, Selection, Toolchain};
+use crate::linter::{Level, Lint, Linter, Location, Replacement};
+use cargo_scout_macros::{info, warn};
 use colored::Colorize;
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
-use std::process::Command;
 
 #[derive(Default)]
-pub struct RustFmt {}
+pub struct RustFmt {
+    toolchain: Toolchain,
+    config_path: Option<PathBuf>,
+    edition: Option<String>,
+    // Options such as `max_width=120`
+    config: Vec<String>,
+}
+
+/// How rustfmt reports the formatting issues.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Emit {
+    /// `--emit json`, only available on nightly
+    Json,
+    /// `--check`, which prints a diff
+    Check,
+}
 
 impl Linter for RustFmt {
     fn lints(&self, working_dir: impl Into<PathBuf>) -> Result<Vec<Lint>, Error> {
@@ -17,33 +33,260 @@ impl Linter for RustFmt {
             "[RustFmt] - checking format for directory {}",
             &working_dir.to_str().unwrap_or("<no directory>")
         );
-        let rustfmt_output = Self::fmt(working_dir)?;
-        lints(&rustfmt_output)
+        let (toolchain, emit) = self.select(&working_dir);
+        let rustfmt_output = self.fmt(&toolchain, emit, working_dir)?;
+        emitted_lints(emit, &rustfmt_output)
+    }
+
+    fn lints_for_files(
+        &self,
+        _root: &Path,
+        members: &[Member],
+        files: &[Vec<PathBuf>],
+    ) -> Result<Vec<Lint>, Error> {
+        let mut lints = Vec::new();
+        for (member, files) in members.iter().zip(files) {
+            lints.extend(self.lints_for_member_files(member, files)?);
+        }
+        Ok(lints)
+    }
+
+    fn lints_for_files_keep_going(
+        &self,
+        _root: &Path,
+        members: &[Member],
+        files: &[Vec<PathBuf>],
+    ) -> (Vec<Lint>, Vec<(usize, Error)>) {
+        let mut lints = Vec::new();
+        let mut failures = Vec::new();
+        for (i, (member, files)) in members.iter().zip(files).enumerate() {
+            match self.lints_for_member_files(member, files) {
+                Ok(member_lints) => lints.extend(member_lints),
+                Err(e) => failures.push((i, e)),
+            }
+        }
+        (lints, failures)
     }
 }
 
 impl RustFmt {
-    fn command_parameters() -> Vec<&'static str> {
-        vec!["+nightly", "fmt", "--", "--emit", "json"]
+    /// The toolchain rustfmt runs with.
+    ///
+    /// It falls back to nightly when nothing selects a toolchain,
+    /// because `--emit json` is not stable yet.
+    /// Other toolchains, or a missing nightly, are checked with `--check` instead.
+    pub fn set_toolchain(&mut self, toolchain: Toolchain) -> &mut Self {
+        self.toolchain = toolchain;
+        self
+    }
+
+    fn toolchain(&self) -> Toolchain {
+        let mut toolchain = self.toolchain.clone();
+        toolchain.set_fallback(Some("nightly".to_string()));
+        toolchain
+    }
+
+    /// The rustfmt configuration file to use,
+    /// instead of the `rustfmt.toml` file found next to the formatted files.
+    pub fn set_config_path(&mut self, config_path: Option<PathBuf>) -> &mut Self {
+        self.config_path = config_path;
+        self
+    }
+
+    /// The edition to format with, instead of the edition of the formatted crates.
+    pub fn set_edition(&mut self, edition: Option<String>) -> &mut Self {
+        self.edition = edition;
+        self
+    }
+
+    /// Rustfmt options overriding the ones of the configuration file,
+    /// as `key=value` strings, such as `max_width=120`.
+    pub fn set_config(&mut self, config: &[String]) -> &mut Self {
+        self.config = config.to_vec();
+        self
+    }
+
+    // Like `cargo fmt`, files are formatted with the edition of their target.
+    // Rustfmt looks for a `rustfmt.toml` file next to them, and honors `#[rustfmt::skip]`.
+    fn lints_for_member_files(
+        &self,
+        member: &Member,
+        files: &[PathBuf],
+    ) -> Result<Vec<Lint>, Error> {
+        if files.is_empty() {
+            return self.lints_for(member);
+        }
+        info!(
+            "[RustFmt] - checking format for {} file(s) of {}",
+            files.len(),
+            &member.name
+        );
+        let (toolchain, emit) = self.select(&member.root);
+        let mut editions: BTreeMap<&str, Vec<&Path>> = BTreeMap::new();
+        for file in files {
+            // Without an edition, rustfmt would format the file as 2015 code
+            let Some(edition) = self.edition.as_deref().or_else(|| member.edition_of(file)) else {
+                info!(
+                    "[RustFmt] - checking the whole crate, the edition of {} is unknown",
+                    file.display()
+                );
+                return self.lints_for(member);
+            };
+            editions.entry(edition).or_default().push(file);
+        }
+        let mut lints = Vec::new();
+        for (edition, files) in editions {
+            match self.rustfmt(&toolchain, emit, &member.root, edition, &files) {
+                Ok(rustfmt_output) => lints.extend(emitted_lints(emit, &rustfmt_output)?),
+                // Rustfmt can't always resolve the modules of a file on its own,
+                // e.g. when they are declared in a macro
+                Err(Error::Command(e)) => {
+                    warn!(
+                        "[RustFmt] - checking the whole crate, the changed files can't be checked on their own: {}",
+                        e
+                    );
+                    return self.lints_for(member);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(lints)
+    }
+
+    fn command_parameters(&self, emit: Emit) -> Vec<String> {
+        let mut params = vec!["fmt".to_string(), "--".to_string()];
+        params.extend(self.rustfmt_parameters(emit));
+        // `cargo fmt` already passes the edition of the crate with `--edition`
+        if let Some(edition) = &self.edition {
+            params.push("--config".to_string());
+            params.push(format!("edition={}", edition));
+        }
+        params
+    }
+
+    // The edition is passed with the files
+    fn rustfmt_parameters(&self, emit: Emit) -> Vec<String> {
+        let mut params: Vec<String> = match emit {
+            Emit::Json => vec!["--emit", "json"],
+            Emit::Check => vec!["--check", "--color", "never"],
+        }
+        .into_iter()
+        .map(String::from)
+        .collect();
+        if let Some(config_path) = &self.config_path {
+            params.push("--config-path".to_string());
+            params.push(config_path.to_string_lossy().to_string());
+        }
+        for option in &self.config {
+            params.push("--config".to_string());
+            params.push(option.clone());
+        }
+        params
     }
 
     // Skipped from code coverage
     // because an external command
     // cannot be easily unit tested
     #[cfg(not(tarpaulin_include))]
-    fn fmt(path: impl AsRef<Path>) -> Result<String, Error> {
-        let fmt_output = Command::new("cargo")
-            .current_dir(path)
-            .args(Self::command_parameters())
-            .output()
-            .expect("failed to run cargo fmt");
-
-        if fmt_output.status.success() {
+    fn select(&self, path: &Path) -> (Selection, Emit) {
+        let nightly = self.toolchain().select(path);
+        let version = nightly.version(path);
+        let (toolchain, version, emit) = if version.as_deref().is_some_and(is_nightly) {
+            (nightly, version, Emit::Json)
+        } else {
+            // Either another toolchain has been selected, or nightly is not installed
+            let toolchain = self.toolchain.select(path);
+            let version = if toolchain == nightly {
+                version
+            } else {
+                toolchain.version(path)
+            };
+            (toolchain, version, Emit::Check)
+        };
+        info!(
+            "[RustFmt] - using {}{}",
+            toolchain,
+            version.map(|v| format!(": {}", v)).unwrap_or_default()
+        );
+        if emit == Emit::Check {
+            info!("[RustFmt] - not a nightly toolchain, checking the format with `--check`");
+        }
+        (toolchain, emit)
+    }
+
+    // Skipped from code coverage
+    // because an external command
+    // cannot be easily unit tested
+    #[cfg(not(tarpaulin_include))]
+    fn fmt(
+        &self,
+        toolchain: &Selection,
+        emit: Emit,
+        path: impl AsRef<Path>,
+    ) -> Result<String, Error> {
+        let fmt_output = output(
+            toolchain
+                .cargo()
+                .current_dir(&path)
+                .args(self.command_parameters(emit)),
+        )?;
+
+        if fmt_output.status.success()
+            || (emit == Emit::Check && found_issues(fmt_output.status.code(), &fmt_output.stderr))
+        {
             Ok(String::from_utf8(fmt_output.stdout)?)
         } else {
-            Err(Error::Command(String::from_utf8(fmt_output.stderr)?))
+            Err(command_error(String::from_utf8(fmt_output.stderr)?))
         }
     }
+
+    // Skipped from code coverage
+    // because an external command
+    // cannot be easily unit tested
+    #[cfg(not(tarpaulin_include))]
+    fn rustfmt(
+        &self,
+        toolchain: &Selection,
+        emit: Emit,
+        path: &Path,
+        edition: &str,
+        files: &[&Path],
+    ) -> Result<String, Error> {
+        let rustfmt_output = output(
+            toolchain
+                .rustfmt()
+                .current_dir(path)
+                .args(self.rustfmt_parameters(emit))
+                .args(["--edition", edition])
+                .args(files),
+        )?;
+
+        if rustfmt_output.status.success()
+            || (emit == Emit::Check
+                && found_issues(rustfmt_output.status.code(), &rustfmt_output.stderr))
+        {
+            Ok(String::from_utf8(rustfmt_output.stdout)?)
+        } else {
+            Err(command_error(String::from_utf8(rustfmt_output.stderr)?))
+        }
+    }
+}
+
+fn emitted_lints(emit: Emit, rustfmt_output: &str) -> Result<Vec<Lint>, Error> {
+    match emit {
+        Emit::Json => lints(rustfmt_output),
+        Emit::Check => Ok(check_lints(rustfmt_output)),
+    }
+}
+
+// `--check` exits with status 1 when there are formatting issues,
+// but also when some files can't be parsed, which is reported on stderr.
+// Warnings, e.g. about unstable options on stable, don't fail the run.
+fn found_issues(status: Option<i32>, stderr: &[u8]) -> bool {
+    status == Some(1)
+        && !String::from_utf8_lossy(stderr)
+            .lines()
+            .any(|l| l.starts_with("error") || l.starts_with("Error"))
 }
 
 #[derive(Deserialize, Debug)]
@@ -61,30 +304,109 @@ struct FmtMismatch {
 }
 
 fn lints(fmt_output: &str) -> Result<Vec<Lint>, Error> {
-    let mut lints = Vec::new();
     let fmt_lints: Vec<FmtLint> = serde_json::from_str(fmt_output)?;
+    let mut lints = to_lints(fmt_lints);
+    // Older rustfmt versions don't end the json mismatches with a newline,
+    // unlike the replaced lines
+    for r in lints.iter_mut().flat_map(|l| l.replacements.iter_mut()) {
+        for text in [&mut r.original, &mut r.replacement] {
+            if !text.is_empty() && !text.ends_with('\n') {
+                text.push('\n');
+            }
+        }
+    }
+    Ok(lints)
+}
+
+// `--check` prints the chunks of each file with issues as a diff:
+// Diff in /path/to/file.rs:12:
+//  a context line
+// -an original line
+// +an expected line
+fn check_lints(check_output: &str) -> Vec<Lint> {
+    let mut fmt_lints: Vec<FmtLint> = Vec::new();
+    // The original line number of the next diff line
+    let mut line = 0;
+    let mut in_mismatch = false;
+    for diff_line in check_output.lines() {
+        if let Some((name, start)) = diff_line.strip_prefix("Diff in ").and_then(diff_header) {
+            if fmt_lints.last().map_or(true, |l| l.name != name) {
+                fmt_lints.push(FmtLint {
+                    name,
+                    mismatches: Vec::new(),
+                });
+            }
+            line = start;
+            in_mismatch = false;
+            continue;
+        }
+        let Some(fmt_lint) = fmt_lints.last_mut() else {
+            continue;
+        };
+        let change = diff_line.chars().next().filter(|c| *c == '-' || *c == '+');
+        let Some(change) = change else {
+            line += 1;
+            in_mismatch = false;
+            continue;
+        };
+        if !in_mismatch {
+            fmt_lint.mismatches.push(FmtMismatch {
+                original_begin_line: line,
+                original_end_line: line,
+                original: String::new(),
+                expected: String::new(),
+            });
+            in_mismatch = true;
+        }
+        if let Some(mismatch) = fmt_lint.mismatches.last_mut() {
+            let content = &diff_line[1..];
+            if change == '-' {
+                mismatch.original_end_line = line;
+                mismatch.original.push_str(content);
+                mismatch.original.push('\n');
+                line += 1;
+            } else {
+                mismatch.expected.push_str(content);
+                mismatch.expected.push('\n');
+            }
+        }
+    }
+    to_lints(fmt_lints)
+}
+
+// Older rustfmt versions print `Diff in /path/to/file.rs at line 12:`
+fn diff_header(header: &str) -> Option<(String, u32)> {
+    let header = header.strip_suffix(':')?;
+    let (name, line) = header
+        .rsplit_once(" at line ")
+        .or_else(|| header.rsplit_once(':'))?;
+    Some((name.to_string(), line.parse().ok()?))
+}
+
+fn to_lints(fmt_lints: Vec<FmtLint>) -> Vec<Lint> {
+    let mut lints = Vec::new();
     for fmt_lint in fmt_lints {
         lints.append(
             &mut fmt_lint
                 .mismatches
                 .iter()
-                .filter_map(|mismatch| {
-                    if let Ok(path) = get_absolute_file_path(fmt_lint.name.clone()) {
-                        Some(Lint {
-                            message: display_mismatch(mismatch, &path),
-                            location: Location {
-                                path,
-                                lines: [mismatch.original_begin_line, mismatch.original_end_line],
-                            },
-                        })
-                    } else {
-                        None
-                    }
+                .map(|mismatch| Lint {
+                    message: display_mismatch(mismatch, &fmt_lint.name),
+                    location: Location {
+                        path: PathBuf::from(&fmt_lint.name),
+                        lines: [mismatch.original_begin_line, mismatch.original_end_line],
+                    },
+                    level: Level::Warning,
+                    replacements: vec![Replacement {
+                        line: mismatch.original_begin_line,
+                        original: mismatch.original.clone(),
+                        replacement: mismatch.expected.clone(),
+                    }],
                 })
                 .collect::<Vec<Lint>>(),
         );
     }
-    Ok(lints)
+    lints
 }
 
 fn display_mismatch(mismatch: &FmtMismatch, path: &str) -> String {
@@ -118,16 +440,65 @@ fn display_mismatch(mismatch: &FmtMismatch, path: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::{
-        display_mismatch, get_absolute_file_path, lints, Error, FmtMismatch, Lint, Location,
-        RustFmt,
+        check_lints, diff_header, display_mismatch, found_issues, lints, Emit, Error, FmtMismatch,
+        Lint, Location, PathBuf, Replacement, RustFmt, Toolchain,
     };
 
     #[test]
     fn test_command_parameters() {
+        let linter = RustFmt::default();
         assert_eq!(
-            vec!["+nightly", "fmt", "--", "--emit", "json"],
-            RustFmt::command_parameters()
+            vec!["fmt", "--", "--emit", "json"],
+            linter.command_parameters(Emit::Json)
         );
+        assert_eq!(
+            vec!["fmt", "--", "--check", "--color", "never"],
+            linter.command_parameters(Emit::Check)
+        );
+        assert_eq!(
+            vec!["--check", "--color", "never"],
+            linter.rustfmt_parameters(Emit::Check)
+        );
+    }
+
+    #[test]
+    fn test_command_parameters_with_config() {
+        let mut linter = RustFmt::default();
+        linter
+            .set_config_path(Some(PathBuf::from("/foo/.config/rustfmt.toml")))
+            .set_edition(Some("2021".to_string()))
+            .set_config(&["max_width=120".to_string(), "hard_tabs=true".to_string()]);
+        let rustfmt_parameters = vec![
+            "--check",
+            "--color",
+            "never",
+            "--config-path",
+            "/foo/.config/rustfmt.toml",
+            "--config",
+            "max_width=120",
+            "--config",
+            "hard_tabs=true",
+        ];
+        assert_eq!(rustfmt_parameters, linter.rustfmt_parameters(Emit::Check));
+        // The edition of the crate is already passed by `cargo fmt`
+        let mut command_parameters = vec!["fmt", "--"];
+        command_parameters.extend(rustfmt_parameters);
+        command_parameters.extend(vec!["--config", "edition=2021"]);
+        assert_eq!(command_parameters, linter.command_parameters(Emit::Check));
+    }
+
+    #[test]
+    fn test_toolchain() {
+        let mut nightly = Toolchain::default();
+        nightly.set_fallback(Some("nightly".to_string()));
+        assert_eq!(nightly, RustFmt::default().toolchain());
+
+        let mut pinned = Toolchain::default();
+        pinned.set_name(Some("nightly-2020-01-01".to_string()));
+        let mut linter = RustFmt::default();
+        linter.set_toolchain(pinned.clone());
+        pinned.set_fallback(Some("nightly".to_string()));
+        assert_eq!(pinned, linter.toolchain());
     }
 
     #[test]
@@ -139,7 +510,7 @@ mod tests {
             expected: "this is a test mismatch".to_string(),
         };
 
-        let path = get_absolute_file_path("src/foo/bar.txt")?;
+        let path = "src/foo/bar.txt".to_string();
 
         let expected_display = format!(
             "Diff in {} at line 1:\n-    this is a test mismatch\n+this is a test mismatch\n",
@@ -159,7 +530,7 @@ mod tests {
             expected: "this is a test mismatch\nthe indent is wrong".to_string(),
         };
 
-        let path = get_absolute_file_path("src/foo/bar.txt")?;
+        let path = "src/foo/bar.txt".to_string();
 
         let expected_display = format!("Diff in {} between lines 1 and 2:\n-    this is a test mismatch\n-  the indent is wrong\n+this is a test mismatch\n+the indent is wrong\n", path);
         let actual_display = display_mismatch(&mismatch, &path);
@@ -171,16 +542,22 @@ mod tests {
     fn test_lints() -> Result<(), crate::error::Error> {
         let fmt_output = r#"[{"name":"cargo-scout/cargo-scout-lib/src/lib.rs","mismatches":[{"original_begin_line":1,"original_end_line":1,"expected_begin_line":1,"expected_end_line":1,"original":"    pub mod config;","expected":"pub mod config;"}]}]"#;
 
-        let path = get_absolute_file_path("cargo-scout/cargo-scout-lib/src/lib.rs")?;
+        let path = "cargo-scout/cargo-scout-lib/src/lib.rs".to_string();
         let expected_lints = vec![Lint {
             location: Location {
                 lines: [1, 1],
-                path: path.clone(),
+                path: PathBuf::from(&path),
             },
             message: format!(
                 "Diff in {} at line 1:\n-    pub mod config;\n+pub mod config;\n",
                 path
             ),
+            replacements: vec![Replacement {
+                line: 1,
+                original: "    pub mod config;\n".to_string(),
+                replacement: "pub mod config;\n".to_string(),
+            }],
+            ..Lint::default()
         }];
 
         let actual_lints = lints(fmt_output).unwrap();
@@ -189,4 +566,97 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_lints_several_lines() -> Result<(), crate::error::Error> {
+        let fmt_output = r#"[{"name":"src/lib.rs","mismatches":[{"original_begin_line":2,"original_end_line":3,"expected_begin_line":2,"expected_end_line":3,"original":"let a=1;\n  let b = 2;\n","expected":"    let a = 1;\n    let b = 2;\n"}]}]"#;
+
+        let expected_replacements = vec![Replacement {
+            line: 2,
+            original: "let a=1;\n  let b = 2;\n".to_string(),
+            replacement: "    let a = 1;\n    let b = 2;\n".to_string(),
+        }];
+        assert_eq!(expected_replacements, lints(fmt_output)?[0].replacements);
+        Ok(())
+    }
+
+    #[test]
+    fn test_found_issues() {
+        assert!(found_issues(Some(1), b""));
+        assert!(found_issues(
+            Some(1),
+            b"Warning: can't set `imports_granularity = Crate`, unstable features are only available in nightly channel.\n"
+        ));
+        // Some diffs may have been printed before the error
+        assert!(!found_issues(
+            Some(1),
+            b"error: expected one of `!` or `::`, found `<eof>`\n --> /foo/src/b.rs:1:4\n"
+        ));
+        assert!(!found_issues(
+            Some(1),
+            b"Error writing files: failed to resolve mod `b`: cannot parse /foo/src/b.rs\n"
+        ));
+        assert!(!found_issues(Some(101), b""));
+        assert!(!found_issues(None, b""));
+    }
+
+    #[test]
+    fn test_diff_header() {
+        assert_eq!(
+            Some(("/foo/src/lib.rs".to_string(), 12)),
+            diff_header("/foo/src/lib.rs:12:")
+        );
+        assert_eq!(
+            Some(("/foo/src/lib.rs".to_string(), 12)),
+            diff_header("/foo/src/lib.rs at line 12:")
+        );
+        assert_eq!(None, diff_header("/foo/src/lib.rs"));
+    }
+
+    #[test]
+    fn test_check_lints() {
+        let check_output = "Diff in /foo/src/lib.rs:1:
+-pub fn a( ) {}
++pub fn a() {}
+ 
+-
+-
+ pub fn b() {
+     let x = 1;
+-    let y=2;
+-    let _ = x+y;
++    let y = 2;
++    let _ = x + y;
+ }
+Diff in /foo/src/lib.rs:20:
+ fn c() {}
++
+ fn d() {}
+Diff in /foo/src/m.rs:1:
+-fn e( ){}
++fn e() {}
+ 
+";
+        let lints = check_lints(check_output);
+        let locations: Vec<(PathBuf, [u32; 2])> = lints
+            .iter()
+            .map(|l| (l.location.path.clone(), l.location.lines))
+            .collect();
+        assert_eq!(
+            vec![
+                (PathBuf::from("/foo/src/lib.rs"), [1, 1]),
+                (PathBuf::from("/foo/src/lib.rs"), [3, 4]),
+                (PathBuf::from("/foo/src/lib.rs"), [7, 8]),
+                // An inserted line
+                (PathBuf::from("/foo/src/lib.rs"), [21, 21]),
+                (PathBuf::from("/foo/src/m.rs"), [1, 1]),
+            ],
+            locations
+        );
+        assert_eq!(
+            "Diff in /foo/src/lib.rs between lines 7 and 8:\n-    let y=2;\n-    let _ = x+y;\n+    let y = 2;\n+    let _ = x + y;\n",
+            lints[2].message
+        );
+        assert!(check_lints("").is_empty());
+    }
 }
@@ -0,0 +1,445 @@
+use crate::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// The toolchain file names rustup looks for, in order.
+pub const TOOLCHAIN_FILE_NAMES: [&str; 2] = ["rust-toolchain", "rust-toolchain.toml"];
+
+/// This struct selects the toolchain a linter runs cargo with.
+///
+/// Like rustup, the toolchain is, in order:
+/// - the one set with `set_name`,
+/// - the one set by the `RUSTUP_TOOLCHAIN` environment variable,
+/// - the one set by a `rust-toolchain` or `rust-toolchain.toml` file,
+///   in the working directory or one of its parents,
+/// - the fallback one, for commands needing e.g. nightly features,
+/// - the default one otherwise.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct Toolchain {
+    name: Option<String>,
+    fallback: Option<String>,
+}
+
+/// The toolchain a command runs with, and where it comes from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Selection {
+    /// Set explicitly
+    Name(String),
+    /// Set by the `RUSTUP_TOOLCHAIN` environment variable
+    Environment(String),
+    /// Set by this toolchain file
+    File(PathBuf),
+    /// The fallback toolchain of the command
+    Fallback(String),
+    /// The default toolchain
+    Default,
+}
+
+impl Toolchain {
+    /// The toolchain name, such as `nightly` or `nightly-2020-01-01`.
+    ///
+    /// A leading `+` is ignored.
+    pub fn set_name(&mut self, name: Option<String>) -> &mut Self {
+        self.name = name.map(|n| n.trim_start_matches('+').to_string());
+        self
+    }
+
+    /// The toolchain used when nothing else selects one.
+    pub fn set_fallback(&mut self, fallback: Option<String>) -> &mut Self {
+        self.fallback = fallback;
+        self
+    }
+
+    /// Selects the toolchain cargo would run with in `dir`.
+    #[must_use]
+    pub fn select(&self, dir: impl AsRef<Path>) -> Selection {
+        self.select_with(
+            dir,
+            env_toolchain(
+                std::env::var("RUSTUP_TOOLCHAIN").ok(),
+                std::env::var("RUSTUP_TOOLCHAIN_SOURCE").ok().as_deref(),
+            ),
+        )
+    }
+
+    fn select_with(&self, dir: impl AsRef<Path>, env_toolchain: Option<String>) -> Selection {
+        if let Some(name) = &self.name {
+            return Selection::Name(name.clone());
+        }
+        if let Some(name) = env_toolchain {
+            return Selection::Environment(name);
+        }
+        if let Some(file) = toolchain_file(dir) {
+            return Selection::File(file);
+        }
+        match &self.fallback {
+            Some(fallback) => Selection::Fallback(fallback.clone()),
+            None => Selection::Default,
+        }
+    }
+}
+
+impl Selection {
+    /// The argument selecting the toolchain on the cargo or rustfmt command line, if any.
+    ///
+    /// Other toolchains are picked up by rustup itself.
+    #[must_use]
+    pub fn cargo_arg(&self) -> Option<String> {
+        match self {
+            Self::Name(name) | Self::Fallback(name) => Some(format!("+{}", name)),
+            Self::Environment(_) | Self::File(_) | Self::Default => None,
+        }
+    }
+
+    /// A cargo command running with the selected toolchain.
+    ///
+    /// The `CARGO` environment variable, set when running as a cargo subcommand,
+    /// points to the cargo binary of the current toolchain.
+    /// Selecting another toolchain, or honoring a toolchain file,
+    /// goes through the rustup `cargo` proxy instead.
+    #[must_use]
+    pub fn cargo(&self) -> Command {
+        self.command("CARGO", "cargo")
+    }
+
+    /// A rustfmt command running with the selected toolchain.
+    ///
+    /// Like `cargo fmt`, it honors the `RUSTFMT` environment variable
+    /// for the current toolchain.
+    #[must_use]
+    pub fn rustfmt(&self) -> Command {
+        self.command("RUSTFMT", "rustfmt")
+    }
+
+    fn command(&self, env_program: &str, program: &str) -> Command {
+        let program = match self {
+            Self::Environment(_) | Self::Default => {
+                std::env::var_os(env_program).unwrap_or_else(|| program.into())
+            }
+            Self::Name(_) | Self::File(_) | Self::Fallback(_) => program.into(),
+        };
+        let mut command = Command::new(program);
+        command.args(self.cargo_arg());
+        if let Self::File(_) = self {
+            // The default toolchain rustup ran us with would win over the file
+            command.env_remove("RUSTUP_TOOLCHAIN");
+        }
+        command
+    }
+
+    /// The version of the selected cargo, such as `cargo 1.43.0-nightly (...)`.
+    #[must_use]
+    pub fn version(&self, dir: impl AsRef<Path>) -> Option<String> {
+        cargo_version(self.cargo(), dir)
+    }
+}
+
+impl fmt::Display for Selection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Name(name) => write!(f, "toolchain {}", name),
+            Self::Environment(name) => write!(f, "toolchain {} (from RUSTUP_TOOLCHAIN)", name),
+            Self::File(path) => write!(f, "the toolchain of {}", path.display()),
+            Self::Fallback(name) => write!(f, "toolchain {} (fallback)", name),
+            Self::Default => write!(f, "the default toolchain"),
+        }
+    }
+}
+
+/// Returns true if a cargo or rustc `--version` output is the one of a nightly toolchain.
+#[must_use]
+pub fn is_nightly(version: &str) -> bool {
+    version.contains("-nightly") || version.contains("-dev")
+}
+
+/// Runs a cargo or rustfmt `command`, and returns its output.
+///
+/// A missing program is reported as `Error::ToolNotFound`.
+#[allow(clippy::missing_errors_doc)]
+pub fn output(command: &mut Command) -> Result<Output, Error> {
+    command.output().map_err(|e| {
+        if e.kind() == io::ErrorKind::NotFound {
+            Error::ToolNotFound(command.get_program().to_string_lossy().to_string())
+        } else {
+            Error::Io(e)
+        }
+    })
+}
+
+/// Turns the error output of a failed cargo or rustfmt command into an `Error`.
+///
+/// The rustup and cargo errors about missing toolchains and components are recognized,
+/// other failures are reported as `Error::Command`.
+#[must_use]
+pub fn command_error(stderr: String) -> Error {
+    stderr
+        .lines()
+        .find_map(missing_tool)
+        .unwrap_or(Error::Command(stderr))
+}
+
+fn missing_tool(line: &str) -> Option<Error> {
+    // error: 'cargo-clippy' is not installed for the toolchain 'stable-x86_64-unknown-linux-gnu'
+    if let Some((command, toolchain)) = line.split_once(" is not installed for the toolchain ") {
+        return Some(Error::ComponentMissing {
+            component: component(quoted(command, '\'')?),
+            toolchain: quoted(toolchain, '\'').map(str::to_string),
+        });
+    }
+    // error: the 'clippy' component which provides the command 'cargo-clippy'
+    // is not available for the 'nightly-2020-01-01-x86_64-unknown-linux-gnu' toolchain
+    if let Some((component, toolchain)) = line.split_once(" component which provides ") {
+        return Some(Error::ComponentMissing {
+            component: quoted(component, '\'')?.to_string(),
+            toolchain: toolchain
+                .split_once(" for the ")
+                .and_then(|(_, t)| quoted(t, '\''))
+                .map(str::to_string),
+        });
+    }
+    // error: toolchain 'beta-x86_64-unknown-linux-gnu' is not installed
+    if let Some((_, toolchain)) = line
+        .strip_suffix(" is not installed")
+        .and_then(|l| l.split_once("toolchain "))
+    {
+        return Some(Error::ToolchainMissing(
+            quoted(toolchain, '\'')?.to_string(),
+        ));
+    }
+    // error: no such command: `clippy`, when cargo doesn't come from rustup
+    let (_, command) = line
+        .split_once("no such command: ")
+        .or_else(|| line.split_once("no such subcommand: "))?;
+    match quoted(command, '`')? {
+        command @ ("clippy" | "clippy-preview" | "fmt") => Some(Error::ComponentMissing {
+            component: component(command),
+            toolchain: None,
+        }),
+        _ => None,
+    }
+}
+
+// The text between the first two `quote`s
+fn quoted(s: &str, quote: char) -> Option<&str> {
+    s.split(quote)
+        .nth(1)
+        .filter(|_| s.matches(quote).count() >= 2)
+}
+
+// The rustup component providing a command
+fn component(command: &str) -> String {
+    match command {
+        "cargo-clippy" | "clippy-driver" | "clippy" | "clippy-preview" => "clippy",
+        "cargo-fmt" | "rustfmt" | "fmt" => "rustfmt",
+        other => other,
+    }
+    .to_string()
+}
+
+// rustup sets `RUSTUP_TOOLCHAIN` for all the processes it runs,
+// `RUSTUP_TOOLCHAIN_SOURCE` tells whether something selected it.
+fn env_toolchain(toolchain: Option<String>, source: Option<&str>) -> Option<String> {
+    match source {
+        Some("default") => None,
+        _ => toolchain.filter(|t| !t.is_empty()),
+    }
+}
+
+fn toolchain_file(dir: impl AsRef<Path>) -> Option<PathBuf> {
+    dir.as_ref().ancestors().find_map(|dir| {
+        TOOLCHAIN_FILE_NAMES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())
+    })
+}
+
+// Skipped from code coverage
+// because an external command
+// cannot be easily unit tested
+#[cfg(not(tarpaulin_include))]
+fn cargo_version(mut command: Command, dir: impl AsRef<Path>) -> Option<String> {
+    let output = command.current_dir(dir).arg("--version").output().ok()?;
+    if output.status.success() {
+        String::from_utf8(output.stdout)
+            .ok()
+            .map(|version| version.trim().to_string())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{command_error, env_toolchain, is_nightly, output, Selection, Toolchain};
+    use crate::error::Error;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_default() {
+        let dir = TempDir::new().unwrap();
+        let toolchain = Toolchain::default();
+        assert_eq!(Selection::Default, toolchain.select_with(dir.path(), None));
+        assert_eq!(None, Selection::Default.cargo_arg());
+    }
+
+    #[test]
+    fn test_name() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("rust-toolchain"), "stable").unwrap();
+        let mut toolchain = Toolchain::default();
+        toolchain
+            .set_name(Some("+nightly-2020-01-01".to_string()))
+            .set_fallback(Some("nightly".to_string()));
+        // An explicit name wins over everything else
+        let selection = toolchain.select_with(dir.path(), Some("beta".to_string()));
+        assert_eq!(Selection::Name("nightly-2020-01-01".to_string()), selection);
+        assert_eq!(
+            Some("+nightly-2020-01-01".to_string()),
+            selection.cargo_arg()
+        );
+    }
+
+    #[test]
+    fn test_environment() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("rust-toolchain"), "stable").unwrap();
+        let mut toolchain = Toolchain::default();
+        toolchain.set_fallback(Some("nightly".to_string()));
+        let selection = toolchain.select_with(dir.path(), Some("beta".to_string()));
+        assert_eq!(Selection::Environment("beta".to_string()), selection);
+        // rustup already selects it
+        assert_eq!(None, selection.cargo_arg());
+    }
+
+    #[test]
+    fn test_env_toolchain() {
+        let beta = Some("beta".to_string());
+        assert_eq!(beta, env_toolchain(beta.clone(), None));
+        assert_eq!(beta, env_toolchain(beta.clone(), Some("cli")));
+        // rustup sets it even when nothing selected a toolchain
+        assert_eq!(None, env_toolchain(beta, Some("default")));
+        assert_eq!(None, env_toolchain(Some(String::new()), None));
+    }
+
+    #[test]
+    fn test_toolchain_file() {
+        let dir = TempDir::new().unwrap();
+        let member = dir.path().join("crates/foo");
+        fs::create_dir_all(&member).unwrap();
+        fs::write(
+            dir.path().join("rust-toolchain.toml"),
+            "[toolchain]\nchannel = \"nightly-2020-01-01\"\n",
+        )
+        .unwrap();
+        let mut toolchain = Toolchain::default();
+        toolchain.set_fallback(Some("nightly".to_string()));
+        // The file is looked up in the parent directories
+        let selection = toolchain.select_with(&member, None);
+        assert_eq!(
+            Selection::File(dir.path().join("rust-toolchain.toml")),
+            selection
+        );
+        assert_eq!(None, selection.cargo_arg());
+    }
+
+    #[test]
+    fn test_fallback() {
+        let dir = TempDir::new().unwrap();
+        let mut toolchain = Toolchain::default();
+        toolchain.set_fallback(Some("nightly".to_string()));
+        let selection = toolchain.select_with(dir.path(), None);
+        assert_eq!(Selection::Fallback("nightly".to_string()), selection);
+        assert_eq!(Some("+nightly".to_string()), selection.cargo_arg());
+    }
+
+    #[test]
+    fn test_commands() {
+        let nightly = Selection::Name("nightly".to_string());
+        for command in &[nightly.cargo(), nightly.rustfmt()] {
+            assert_eq!(vec!["+nightly"], command.get_args().collect::<Vec<_>>());
+        }
+        assert_eq!("rustfmt", nightly.rustfmt().get_program());
+        let file = Selection::File(PathBuf::from("rust-toolchain"));
+        assert_eq!(0, file.rustfmt().get_args().count());
+        assert!(file
+            .rustfmt()
+            .get_envs()
+            .any(|(k, v)| k == "RUSTUP_TOOLCHAIN" && v.is_none()));
+    }
+
+    #[test]
+    fn test_is_nightly() {
+        assert!(is_nightly("cargo 1.43.0-nightly (9d32b7b01 2020-02-03)"));
+        assert!(is_nightly("rustc 1.45.0-dev"));
+        assert!(!is_nightly("cargo 1.41.0 (626f0f40e 2019-12-03)"));
+        assert!(!is_nightly("cargo 1.42.0-beta.1 (4dc3c17ec 2020-02-01)"));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            "toolchain nightly",
+            Selection::Name("nightly".to_string()).to_string()
+        );
+        assert_eq!(
+            "the toolchain of /foo/rust-toolchain",
+            Selection::File(PathBuf::from("/foo/rust-toolchain")).to_string()
+        );
+        assert_eq!("the default toolchain", Selection::Default.to_string());
+    }
+
+    #[test]
+    fn test_command_error() {
+        let component_missing = |stderr: &str| match command_error(stderr.to_string()) {
+            Error::ComponentMissing {
+                component,
+                toolchain,
+            } => Some((component, toolchain)),
+            _ => None,
+        };
+        let nightly = Some("nightly-x86_64-unknown-linux-gnu".to_string());
+        assert_eq!(
+            Some(("clippy".to_string(), nightly.clone())),
+            component_missing(
+                "error: 'cargo-clippy' is not installed for the toolchain 'nightly-x86_64-unknown-linux-gnu'\nTo install, run `rustup component add clippy --toolchain nightly-x86_64-unknown-linux-gnu`"
+            )
+        );
+        assert_eq!(
+            Some(("rustfmt".to_string(), nightly)),
+            component_missing(
+                "error: the 'rustfmt' component which provides the command 'cargo-fmt' is not available for the 'nightly-x86_64-unknown-linux-gnu' toolchain"
+            )
+        );
+        assert_eq!(
+            Some(("clippy".to_string(), None)),
+            component_missing("error: no such command: `clippy`\n\nhelp: view all installed commands with `cargo --list`")
+        );
+        assert!(matches!(
+            command_error("error: toolchain 'beta-x86_64-unknown-linux-gnu' is not installed\nhelp: run `rustup toolchain install beta-x86_64-unknown-linux-gnu` to install it".to_string()),
+            Error::ToolchainMissing(t) if t == "beta-x86_64-unknown-linux-gnu"
+        ));
+        // Other failures are reported as they are
+        assert!(matches!(
+            command_error("error: no such command: `foo`".to_string()),
+            Error::Command(_)
+        ));
+        assert!(matches!(
+            command_error("error[E0308]: mismatched types".to_string()),
+            Error::Command(_)
+        ));
+    }
+
+    #[test]
+    fn test_tool_not_found() {
+        assert!(matches!(
+            output(&mut Command::new("cargo-scout-missing-tool")),
+            Err(Error::ToolNotFound(tool)) if tool == "cargo-scout-missing-tool"
+        ));
+    }
+}
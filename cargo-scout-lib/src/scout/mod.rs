@@ -1,9 +1,13 @@
-use crate::config::Config;
-use crate::linter::{Lint, Linter};
+use crate::config::{Config, Member};
+use crate::filter::generated::GeneratedFiles;
+use crate::filter::PathFilter;
+use crate::linter::{Level, Lint, Linter};
+use crate::utils::{paths_match, relative_path};
 use crate::vcs::{Section, VCS};
 use cargo_scout_macros::info;
 use colored::Colorize;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
 
 pub struct Scout<V, C, L>
 where
@@ -14,6 +18,37 @@ where
     vcs: V,
     config: C,
     linter: L,
+    filter: PathFilter,
+    generated: GeneratedFiles,
+    verbose: bool,
+    keep_going: bool,
+}
+
+/// The outcome of a `Scout` run.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct Report {
+    /// The lints intersecting the diff, compilation errors included
+    pub lints: Vec<Lint>,
+    /// The compilation errors outside of the diff
+    pub errors: Vec<Lint>,
+    /// The members that failed to lint, in keep going mode
+    pub failures: Vec<Failure>,
+    /// The diff sections the lints were matched against,
+    /// once the filtered and generated files are skipped.
+    ///
+    /// Their paths are relative to the repository root.
+    pub sections: Vec<Section>,
+}
+
+/// A workspace member the linter failed on.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Failure {
+    /// The member name
+    pub member: String,
+    /// The error message
+    pub error: String,
+    /// What to do to fix the error, if there's more to it than its message
+    pub hint: Option<String>,
 }
 
 impl<V, C, L> Scout<V, C, L>
@@ -27,38 +62,196 @@ where
             vcs,
             config,
             linter,
+            filter: PathFilter::default(),
+            generated: GeneratedFiles::default(),
+            verbose: false,
+            keep_going: false,
         }
     }
+
+    pub fn set_verbose(&mut self, verbose: bool) -> &mut Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Only files matching the `PathFilter` will be checked.
+    pub fn set_filter(&mut self, filter: PathFilter) -> &mut Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Generated files will not be checked.
+    pub fn set_generated(&mut self, generated: GeneratedFiles) -> &mut Self {
+        self.generated = generated;
+        self
+    }
+
+    /// In keep going mode, a member failing to lint doesn't stop the run:
+    /// the other members are linted, and the failure is part of the `Report`.
+    pub fn set_keep_going(&mut self, keep_going: bool) -> &mut Self {
+        self.keep_going = keep_going;
+        self
+    }
+
+    /// The root of the repository the paths of the lints returned by `run` are relative to.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn repo_root(&self) -> Result<PathBuf, crate::error::Error> {
+        self.vcs.root(std::fs::canonicalize(self.config.root())?)
+    }
+
+    /// Returns the lints intersecting the diff.
     #[allow(clippy::missing_errors_doc)]
     pub fn run(&self) -> Result<Vec<Lint>, crate::error::Error> {
-        let current_dir = std::fs::canonicalize(std::env::current_dir()?)?;
-        let diff_sections = self.vcs.sections(current_dir.clone())?;
+        Ok(self.report()?.lints)
+    }
+
+    /// Returns the lints intersecting the diff,
+    /// the compilation errors found elsewhere, and the diff sections.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn report(&self) -> Result<Report, crate::error::Error> {
+        // Members and relative lint paths are anchored on the project root,
+        // so cargo-scout can be run from any directory.
+        let root = std::fs::canonicalize(self.config.root())?;
+        // Diff sections are relative to the repository root
+        let repo_root = self.vcs.root(&root)?;
+        let mut skipped_files = BTreeSet::new();
+        let diff_sections = self.relevant_sections(&root, &repo_root, &mut skipped_files)?;
         let mut lints = Vec::new();
-        let config_members = self.config.members();
-        let members = config_members.iter().map(|m| {
-            let mut member = current_dir.clone();
-            member.push(m);
-            member
-        });
+        let members: Vec<Member> = self
+            .config
+            .resolve_members()
+            .iter()
+            .map(|m| m.anchored_on(&root))
+            .collect();
+        let member_dirs: Vec<Vec<PathBuf>> = members.iter().map(Member::dirs).collect();
         // There's no need to run the linter on members where no changes have been made
-        let relevant_members = members.filter(|m| diff_in_member(m, &diff_sections));
-        for m in relevant_members {
-            lints.extend(self.linter.lints(current_dir.clone().join(m))?);
+        let mut changed_files: BTreeMap<usize, Vec<PathBuf>> = BTreeMap::new();
+        for s in &diff_sections {
+            let file = repo_root.join(&s.file_name);
+            if let Some(m) = owning_member(&file, &member_dirs) {
+                let files = changed_files.entry(m).or_default();
+                if !files.contains(&file) {
+                    files.push(file);
+                }
+            }
+        }
+        let (relevant_members, changed_files): (Vec<Member>, Vec<Vec<PathBuf>>) = changed_files
+            .into_iter()
+            .map(|(m, files)| (members[m].clone(), files))
+            .unzip();
+        let failures = if relevant_members.is_empty() {
+            Vec::new()
+        } else {
+            self.lint_members(&root, &relevant_members, &changed_files, &mut lints)?
+        };
+        for l in &mut lints {
+            l.location.path = relative_path(&repo_root, root.join(&l.location.path));
+        }
+        // The code doesn't compile wherever the errors are,
+        // so they are kept even in filtered or generated files.
+        // An error may have several spans, only its first one is kept.
+        let mut errors: Vec<Lint> = Vec::new();
+        for l in lints.iter().filter(|l| l.level == Level::Error) {
+            if !errors.iter().any(|e| e.message == l.message) {
+                errors.push(l.clone());
+            }
+        }
+        lints.retain(|l| is_relevant(&self.filter, &root, &repo_root.join(&l.location.path)));
+        lints.retain(|l| {
+            let generated = self
+                .generated
+                .is_generated(&root, repo_root.join(&l.location.path));
+            if generated {
+                skipped_files.insert(l.location.path.clone());
+            }
+            !generated
+        });
+        if self.verbose {
+            info!(
+                "[Scout] - skipped {} generated file(s)",
+                skipped_files.len()
+            );
         }
         info!("[Scout] - checking for intersections");
-        Ok(lints_from_diff(&lints, &diff_sections))
+        let mut lints = lints_from_diff(&lints, &diff_sections);
+        dedup_lints(&mut lints);
+        // Errors with a span in the diff are reported with the lints
+        errors.retain(|e| !lints.iter().any(|l| l.message == e.message));
+        Ok(Report {
+            lints,
+            errors,
+            failures,
+            sections: diff_sections,
+        })
     }
-}
 
-fn diff_in_member(member: &PathBuf, sections: &[Section]) -> bool {
-    if let Some(m) = member.to_str() {
-        for s in sections {
-            if s.file_name.starts_with(&m) {
-                return true;
-            }
+    // In keep going mode, the linter attributes its failures to the members,
+    // and the other members are linted all the same.
+    fn lint_members(
+        &self,
+        root: &Path,
+        members: &[Member],
+        files: &[Vec<PathBuf>],
+        lints: &mut Vec<Lint>,
+    ) -> Result<Vec<Failure>, crate::error::Error> {
+        if !self.keep_going {
+            lints.extend(self.linter.lints_for_files(root, members, files)?);
+            return Ok(Vec::new());
         }
+        let (member_lints, failures) = self.linter.lints_for_files_keep_going(root, members, files);
+        lints.extend(member_lints);
+        Ok(failures
+            .into_iter()
+            .map(|(i, error)| Failure {
+                member: members[i].name.clone(),
+                error: error.to_string(),
+                hint: error.hint(),
+            })
+            .collect())
+    }
+
+    fn relevant_sections(
+        &self,
+        root: &Path,
+        repo_root: &Path,
+        skipped_files: &mut BTreeSet<PathBuf>,
+    ) -> Result<Vec<Section>, crate::error::Error> {
+        let mut diff_sections = self.vcs.sections(root)?;
+        diff_sections.retain(|s| is_relevant(&self.filter, root, &repo_root.join(&s.file_name)));
+        diff_sections.retain(|s| {
+            let generated = self
+                .generated
+                .is_generated(root, repo_root.join(&s.file_name));
+            if generated {
+                skipped_files.insert(s.file_name.clone());
+            }
+            !generated
+        });
+        Ok(diff_sections)
     }
-    false
+}
+
+// Globs are matched against paths relative to the project root
+fn is_relevant(filter: &PathFilter, root: &Path, path: &Path) -> bool {
+    filter.is_match(path.strip_prefix(root).unwrap_or(path))
+}
+
+// A file belongs to the member owning the longest directory it lives in,
+// so nested members don't claim each other's files.
+fn owning_member(path: &Path, member_dirs: &[Vec<PathBuf>]) -> Option<usize> {
+    member_dirs
+        .iter()
+        .enumerate()
+        .filter_map(|(i, dirs)| {
+            dirs.iter()
+                .filter(|dir| path.starts_with(dir))
+                .map(|dir| dir.components().count())
+                .max()
+                .map(|depth| (depth, i))
+        })
+        // On a tie, the first member wins
+        .max_by(|(a, i), (b, j)| a.cmp(b).then(j.cmp(i)))
+        .map(|(_, i)| i)
 }
 
 // Check if lint and git_section have overlapped lines
@@ -70,8 +263,7 @@ fn lines_in_range(lint: &Lint, git_section: &Section) -> bool {
 }
 
 fn files_match(lint: &Lint, git_section: &Section) -> bool {
-    // Git diff paths and clippy paths don't get along too well on Windows...
-    lint.location.path.replace("\\", "/") == git_section.file_name.replace("\\", "/")
+    paths_match(&lint.location.path, &git_section.file_name)
 }
 
 fn lints_from_diff(lints: &[Lint], diffs: &[Section]) -> Vec<Lint> {
@@ -87,17 +279,33 @@ fn lints_from_diff(lints: &[Lint], diffs: &[Section]) -> Vec<Lint> {
     lints_in_diff
 }
 
+// Clippy reports the lints of a file once per target it belongs to,
+// e.g. for a library checked both as the lib and the test target.
+fn dedup_lints(lints: &mut Vec<Lint>) {
+    let mut seen = HashSet::new();
+    lints.retain(|l| {
+        seen.insert((
+            l.message.clone(),
+            l.location.path.clone(),
+            l.location.lines,
+            l.level,
+        ))
+    });
+}
+
 #[cfg(test)]
 mod scout_tests {
-    use super::{Scout, Section, VCS};
-    use crate::config::Config;
+    use super::{Failure, Report, Scout, Section, VCS};
+    use crate::config::{Config, Member};
     use crate::error::Error;
-    use crate::linter::{Lint, Linter, Location};
-    use crate::utils::get_absolute_file_path;
+    use crate::filter::generated::GeneratedFiles;
+    use crate::filter::PathFilter;
+    use crate::linter::{Level, Lint, Linter, Location};
     use std::cell::RefCell;
     use std::clone::Clone;
     use std::path::{Path, PathBuf};
     use std::rc::Rc;
+
     struct TestVCS {
         sections: Vec<Section>,
         sections_called: RefCell<bool>,
@@ -181,7 +389,7 @@ mod scout_tests {
     #[test]
     fn test_scout_no_workspace_one_diff() -> Result<(), crate::error::Error> {
         let diff = vec![Section {
-            file_name: get_absolute_file_path("foo/bar.rs")?,
+            file_name: PathBuf::from("foo/bar.rs"),
             line_start: 0,
             line_end: 10,
         }];
@@ -190,25 +398,28 @@ mod scout_tests {
             Lint {
                 location: Location {
                     lines: [2, 2],
-                    path: get_absolute_file_path("foo/bar.rs")?,
+                    path: PathBuf::from("foo/bar.rs"),
                 },
                 message: "Test lint".to_string(),
+                ..Lint::default()
             },
             Lint {
                 location: Location {
                     lines: [12, 22],
-                    path: get_absolute_file_path("foo/bar.rs")?,
+                    path: PathBuf::from("foo/bar.rs"),
                 },
                 message: "This lint is not in diff".to_string(),
+                ..Lint::default()
             },
         ];
 
         let expected_lints_from_diff = vec![Lint {
             location: Location {
                 lines: [2, 2],
-                path: get_absolute_file_path("foo/bar.rs")?,
+                path: PathBuf::from("foo/bar.rs"),
             },
             message: "Test lint".to_string(),
+            ..Lint::default()
         }];
 
         let linter = TestLinter::with_lints(lints);
@@ -230,7 +441,7 @@ mod scout_tests {
     #[test]
     fn test_scout_no_workspace_one_diff_not_relevant_member() -> Result<(), crate::error::Error> {
         let diff = vec![Section {
-            file_name: get_absolute_file_path("baz/bar.rs")?,
+            file_name: PathBuf::from("baz/bar.rs"),
             line_start: 0,
             line_end: 10,
         }];
@@ -253,12 +464,12 @@ mod scout_tests {
     fn test_scout_in_workspace() -> Result<(), crate::error::Error> {
         let diff = vec![
             Section {
-                file_name: get_absolute_file_path("member1/bar.rs")?,
+                file_name: PathBuf::from("member1/bar.rs"),
                 line_start: 0,
                 line_end: 10,
             },
             Section {
-                file_name: get_absolute_file_path("member2/bar.rs")?,
+                file_name: PathBuf::from("member2/bar.rs"),
                 line_start: 0,
                 line_end: 10,
             },
@@ -283,12 +494,592 @@ mod scout_tests {
         assert_eq!(expected_times_called, *actual_times_called.borrow());
         Ok(())
     }
+
+    #[test]
+    fn test_scout_nested_members() -> Result<(), crate::error::Error> {
+        let diff = vec![
+            Section {
+                file_name: PathBuf::from("foo/bar/baz.rs"),
+                line_start: 0,
+                line_end: 10,
+            },
+            Section {
+                file_name: PathBuf::from("foo-bar/baz.rs"),
+                line_start: 0,
+                line_end: 10,
+            },
+        ];
+        let linter = TestLinter::new();
+        let vcs = TestVCS::new(diff);
+        let config = TestConfig::new(vec![
+            "foo".to_string(),
+            "foo/bar".to_string(),
+            "foo-bar".to_string(),
+        ]);
+        // `foo` owns none of the changes
+        let expected_times_called = 2;
+        let actual_times_called = Rc::clone(&linter.lints_times_called);
+        let scout = Scout::new(vcs, config, linter);
+        let _ = scout.run()?;
+        assert_eq!(expected_times_called, *actual_times_called.borrow());
+        Ok(())
+    }
+
+    type MemberFiles = Vec<(String, Vec<PathBuf>)>;
+
+    // Records the changed files it is given, by member name
+    struct FilesLinter {
+        files: Rc<RefCell<MemberFiles>>,
+    }
+    impl Linter for FilesLinter {
+        fn lints(
+            &self,
+            _working_dir: impl Into<PathBuf>,
+        ) -> Result<Vec<Lint>, crate::error::Error> {
+            Ok(Vec::new())
+        }
+
+        fn lints_for_files(
+            &self,
+            _root: &Path,
+            members: &[Member],
+            files: &[Vec<PathBuf>],
+        ) -> Result<Vec<Lint>, crate::error::Error> {
+            for (member, files) in members.iter().zip(files) {
+                self.files
+                    .borrow_mut()
+                    .push((member.name.clone(), files.clone()));
+            }
+            Ok(Vec::new())
+        }
+    }
+
+    #[test]
+    fn test_scout_changed_files() -> Result<(), crate::error::Error> {
+        let section = |file_name: &str, line_start: u32| Section {
+            file_name: PathBuf::from(file_name),
+            line_start,
+            line_end: line_start + 1,
+        };
+        let diff = vec![
+            section("foo/bar/baz.rs", 1),
+            section("foo/lib.rs", 1),
+            section("foo/bar/baz.rs", 10),
+        ];
+        let files = Rc::new(RefCell::new(Vec::new()));
+        let linter = FilesLinter {
+            files: Rc::clone(&files),
+        };
+        let vcs = TestVCS::new(diff);
+        let config = TestConfig::new(vec!["foo".to_string(), "foo/bar".to_string()]);
+        let scout = Scout::new(vcs, config, linter);
+        let _ = scout.run()?;
+        // Each file is passed once, to the member owning it, as an absolute path
+        let root = std::fs::canonicalize(".")?;
+        assert_eq!(
+            vec![
+                ("foo".to_string(), vec![root.join("foo/lib.rs")]),
+                ("foo/bar".to_string(), vec![root.join("foo/bar/baz.rs")]),
+            ],
+            *files.borrow()
+        );
+        Ok(())
+    }
+
+    // Fails on the members named `bad`, or on several members at once
+    struct FailingLinter {
+        calls: Rc<RefCell<usize>>,
+    }
+    impl Linter for FailingLinter {
+        fn lints(
+            &self,
+            _working_dir: impl Into<PathBuf>,
+        ) -> Result<Vec<Lint>, crate::error::Error> {
+            Ok(Vec::new())
+        }
+
+        fn lints_for_files(
+            &self,
+            _root: &Path,
+            members: &[Member],
+            files: &[Vec<PathBuf>],
+        ) -> Result<Vec<Lint>, crate::error::Error> {
+            *self.calls.borrow_mut() += 1;
+            if members.len() > 1 {
+                return Err(Error::Command("clippy failed".to_string()));
+            }
+            if members.iter().any(|m| m.name == "bad") {
+                return Err(Error::ToolchainMissing("nightly".to_string()));
+            }
+            Ok(files
+                .iter()
+                .flatten()
+                .map(|file| Lint {
+                    location: Location {
+                        lines: [1, 1],
+                        path: file.clone(),
+                    },
+                    message: "Test lint".to_string(),
+                    ..Lint::default()
+                })
+                .collect())
+        }
+    }
+
+    #[test]
+    fn test_scout_keep_going() -> Result<(), crate::error::Error> {
+        let section = |file_name: &str| Section {
+            file_name: PathBuf::from(file_name),
+            line_start: 1,
+            line_end: 2,
+        };
+        let diff = vec![section("bad/lib.rs"), section("good/lib.rs")];
+        let calls = Rc::new(RefCell::new(0));
+        let linter = FailingLinter {
+            calls: Rc::clone(&calls),
+        };
+        let vcs = TestVCS::new(diff.clone());
+        let config = TestConfig::new(vec!["bad".to_string(), "good".to_string()]);
+        let mut scout = Scout::new(vcs, config, linter);
+        assert!(scout.report().is_err());
+
+        scout.set_keep_going(true);
+        let report = scout.report()?;
+        // The members are linted one by one after the first failure
+        assert_eq!(4, *calls.borrow());
+        assert_eq!(
+            vec![PathBuf::from("good/lib.rs")],
+            report
+                .lints
+                .iter()
+                .map(|l| l.location.path.clone())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![Failure {
+                member: "bad".to_string(),
+                error: "Toolchain error: nightly is not installed".to_string(),
+                hint: Some("run `rustup toolchain install nightly`".to_string()),
+            }],
+            report.failures
+        );
+
+        // A single failing member is not linted again
+        let linter = FailingLinter {
+            calls: Rc::clone(&calls),
+        };
+        let config = TestConfig::new(vec!["bad".to_string()]);
+        let mut scout = Scout::new(TestVCS::new(diff), config, linter);
+        scout.set_keep_going(true);
+        assert_eq!(1, scout.report()?.failures.len());
+        assert_eq!(5, *calls.borrow());
+        Ok(())
+    }
+
+    // Attributes its failure to the first member, and lints the other ones
+    struct AttributingLinter {
+        calls: Rc<RefCell<usize>>,
+    }
+    impl Linter for AttributingLinter {
+        fn lints(
+            &self,
+            _working_dir: impl Into<PathBuf>,
+        ) -> Result<Vec<Lint>, crate::error::Error> {
+            Ok(Vec::new())
+        }
+
+        fn lints_for_files_keep_going(
+            &self,
+            _root: &Path,
+            _members: &[Member],
+            files: &[Vec<PathBuf>],
+        ) -> (Vec<Lint>, Vec<(usize, crate::error::Error)>) {
+            *self.calls.borrow_mut() += 1;
+            let lints = files[1..]
+                .iter()
+                .flatten()
+                .map(|file| Lint {
+                    location: Location {
+                        lines: [1, 1],
+                        path: file.clone(),
+                    },
+                    message: "Test lint".to_string(),
+                    ..Lint::default()
+                })
+                .collect();
+            (
+                lints,
+                vec![(0, Error::Command("clippy failed".to_string()))],
+            )
+        }
+    }
+
+    #[test]
+    fn test_scout_keep_going_attributed_failures() -> Result<(), crate::error::Error> {
+        let section = |file_name: &str| Section {
+            file_name: PathBuf::from(file_name),
+            line_start: 1,
+            line_end: 2,
+        };
+        let diff = vec![section("bad/lib.rs"), section("good/lib.rs")];
+        let calls = Rc::new(RefCell::new(0));
+        let linter = AttributingLinter {
+            calls: Rc::clone(&calls),
+        };
+        let vcs = TestVCS::new(diff);
+        let config = TestConfig::new(vec!["bad".to_string(), "good".to_string()]);
+        let mut scout = Scout::new(vcs, config, linter);
+        scout.set_keep_going(true);
+        let report = scout.report()?;
+        // The members aren't linted again
+        assert_eq!(1, *calls.borrow());
+        assert_eq!(
+            vec![PathBuf::from("good/lib.rs")],
+            report
+                .lints
+                .iter()
+                .map(|l| l.location.path.clone())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![Failure {
+                member: "bad".to_string(),
+                error: "Command error: clippy failed".to_string(),
+                hint: None,
+            }],
+            report.failures
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_scout_normalized_lint_paths() -> Result<(), crate::error::Error> {
+        let diff = vec![Section {
+            file_name: PathBuf::from("foo/bar.rs"),
+            line_start: 0,
+            line_end: 10,
+        }];
+        // Linters may report absolute paths, or paths with `..` segments
+        let lints = vec![
+            Lint {
+                location: Location {
+                    lines: [2, 2],
+                    path: std::fs::canonicalize(".")?.join("foo/bar.rs"),
+                },
+                message: "Absolute path".to_string(),
+                ..Lint::default()
+            },
+            Lint {
+                location: Location {
+                    lines: [3, 3],
+                    path: PathBuf::from("foo/../foo/./bar.rs"),
+                },
+                message: "Relative path".to_string(),
+                ..Lint::default()
+            },
+        ];
+        let expected_lints_from_diff = vec![
+            Lint {
+                location: Location {
+                    lines: [2, 2],
+                    path: PathBuf::from("foo/bar.rs"),
+                },
+                message: "Absolute path".to_string(),
+                ..Lint::default()
+            },
+            Lint {
+                location: Location {
+                    lines: [3, 3],
+                    path: PathBuf::from("foo/bar.rs"),
+                },
+                message: "Relative path".to_string(),
+                ..Lint::default()
+            },
+        ];
+
+        let linter = TestLinter::with_lints(lints);
+        let vcs = TestVCS::new(diff);
+        let config = TestConfig::new(vec!["foo".to_string()]);
+        let scout = Scout::new(vcs, config, linter);
+        let actual_lints_from_diff = scout.run()?;
+        assert_eq!(expected_lints_from_diff, actual_lints_from_diff);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scout_duplicate_lints() -> Result<(), crate::error::Error> {
+        let diff = vec![Section {
+            file_name: PathBuf::from("foo/bar.rs"),
+            line_start: 0,
+            line_end: 10,
+        }];
+        let lint = |path: PathBuf, lines: [u32; 2]| Lint {
+            location: Location { path, lines },
+            message: "Duplicate lint".to_string(),
+            ..Lint::default()
+        };
+        // The lib and the test targets both report the lint
+        let lints = vec![
+            lint(PathBuf::from("foo/bar.rs"), [2, 2]),
+            lint(std::fs::canonicalize(".")?.join("foo/bar.rs"), [2, 2]),
+            lint(PathBuf::from("foo/bar.rs"), [3, 3]),
+            Lint {
+                level: Level::Error,
+                ..lint(PathBuf::from("foo/bar.rs"), [3, 3])
+            },
+        ];
+        let expected_lints_from_diff = vec![
+            lint(PathBuf::from("foo/bar.rs"), [2, 2]),
+            lint(PathBuf::from("foo/bar.rs"), [3, 3]),
+            Lint {
+                level: Level::Error,
+                ..lint(PathBuf::from("foo/bar.rs"), [3, 3])
+            },
+        ];
+
+        let linter = TestLinter::with_lints(lints);
+        let vcs = TestVCS::new(diff);
+        let config = TestConfig::new(vec!["foo".to_string()]);
+        let scout = Scout::new(vcs, config, linter);
+        assert_eq!(expected_lints_from_diff, scout.run()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scout_filter() -> Result<(), crate::error::Error> {
+        let diff = vec![
+            Section {
+                file_name: PathBuf::from("foo/bar.rs"),
+                line_start: 0,
+                line_end: 10,
+            },
+            Section {
+                file_name: PathBuf::from("foo/generated/baz.rs"),
+                line_start: 0,
+                line_end: 10,
+            },
+        ];
+        let lints = vec![
+            Lint {
+                location: Location {
+                    lines: [2, 2],
+                    path: PathBuf::from("foo/bar.rs"),
+                },
+                message: "Test lint".to_string(),
+                ..Lint::default()
+            },
+            Lint {
+                location: Location {
+                    lines: [2, 2],
+                    path: PathBuf::from("foo/generated/baz.rs"),
+                },
+                message: "This lint is in an excluded file".to_string(),
+                ..Lint::default()
+            },
+        ];
+        let expected_lints_from_diff = vec![lints[0].clone()];
+
+        let linter = TestLinter::with_lints(lints);
+        let vcs = TestVCS::new(diff);
+        let config = TestConfig::new(vec!["foo".to_string()]);
+        let mut scout = Scout::new(vcs, config, linter);
+        scout.set_filter(PathFilter::new(&[], &["**/generated/**".to_string()])?);
+        let actual_lints_from_diff = scout.run()?;
+        assert_eq!(expected_lints_from_diff, actual_lints_from_diff);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scout_report() -> Result<(), crate::error::Error> {
+        let diff = vec![Section {
+            file_name: PathBuf::from("foo/bar.rs"),
+            line_start: 0,
+            line_end: 10,
+        }];
+        let lint = |path: &str, line: u32, level: Level| Lint {
+            location: Location {
+                lines: [line, line],
+                path: PathBuf::from(path),
+            },
+            message: format!("{:?} in {}:{}", level, path, line),
+            level,
+            ..Lint::default()
+        };
+        let span = |message: &str, line: u32| Lint {
+            message: message.to_string(),
+            ..lint("foo/bar.rs", line, Level::Error)
+        };
+        let lints = vec![
+            lint("foo/bar.rs", 2, Level::Error),
+            lint("foo/bar.rs", 20, Level::Error),
+            lint("foo/bar.rs", 20, Level::Warning),
+            lint("foo/generated/baz.rs", 2, Level::Error),
+            // Reported for several targets
+            lint("foo/bar.rs", 20, Level::Error),
+            // Errors with several spans
+            span("partly in the diff", 3),
+            span("partly in the diff", 30),
+            span("outside of the diff", 40),
+            span("outside of the diff", 50),
+        ];
+        let expected_report = Report {
+            lints: vec![lints[0].clone(), lints[5].clone()],
+            errors: vec![lints[1].clone(), lints[3].clone(), lints[7].clone()],
+            failures: Vec::new(),
+            sections: diff.clone(),
+        };
+
+        let linter = TestLinter::with_lints(lints);
+        let vcs = TestVCS::new(diff);
+        let config = TestConfig::new(vec!["foo".to_string()]);
+        let mut scout = Scout::new(vcs, config, linter);
+        scout.set_filter(PathFilter::new(&[], &["**/generated/**".to_string()])?);
+        // Errors outside of the diff are reported on their own
+        assert_eq!(expected_report, scout.report()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scout_generated_files() -> Result<(), crate::error::Error> {
+        let diff = vec![
+            Section {
+                file_name: PathBuf::from("foo/bar.rs"),
+                line_start: 0,
+                line_end: 10,
+            },
+            Section {
+                file_name: PathBuf::from("foo/bar_pb.rs"),
+                line_start: 0,
+                line_end: 10,
+            },
+        ];
+        let lints = vec![
+            Lint {
+                location: Location {
+                    lines: [2, 2],
+                    path: PathBuf::from("foo/bar.rs"),
+                },
+                message: "Test lint".to_string(),
+                ..Lint::default()
+            },
+            Lint {
+                location: Location {
+                    lines: [2, 2],
+                    path: PathBuf::from("foo/bar_pb.rs"),
+                },
+                message: "This lint is in a generated file".to_string(),
+                ..Lint::default()
+            },
+            Lint {
+                location: Location {
+                    lines: [2, 2],
+                    path: PathBuf::from("target/debug/build/foo-1234/out/foo.rs"),
+                },
+                message: "This lint is in a build script output".to_string(),
+                ..Lint::default()
+            },
+        ];
+        let expected_lints_from_diff = vec![lints[0].clone()];
+
+        let linter = TestLinter::with_lints(lints);
+        let vcs = TestVCS::new(diff.clone());
+        let config = TestConfig::new(vec!["foo".to_string()]);
+        let mut scout = Scout::new(vcs, config, linter);
+        scout.set_generated(GeneratedFiles::new(&["**/*_pb.rs".to_string()])?);
+        let report = scout.report()?;
+        assert_eq!(expected_lints_from_diff, report.lints);
+        // Sections of generated files are skipped too
+        assert_eq!(vec![diff[0].clone()], report.sections);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scout_filter_excludes_whole_diff() -> Result<(), crate::error::Error> {
+        let diff = vec![Section {
+            file_name: PathBuf::from("vendor/bar.rs"),
+            line_start: 0,
+            line_end: 10,
+        }];
+        let linter = TestLinter::new();
+        let vcs = TestVCS::new(diff);
+        let config = TestConfig::new(vec!["vendor".to_string()]);
+        let expected_times_called = 0;
+        let actual_times_called = Rc::clone(&linter.lints_times_called);
+        let mut scout = Scout::new(vcs, config, linter);
+        scout.set_filter(PathFilter::new(&[], &["vendor/**".to_string()])?);
+        // There is no relevant diff left, so the linter won't run
+        let _ = scout.run()?;
+        assert_eq!(expected_times_called, *actual_times_called.borrow());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod owning_member_tests {
+    use super::owning_member;
+    use std::path::{Path, PathBuf};
+
+    fn member_dirs(members: &[&[&str]]) -> Vec<Vec<PathBuf>> {
+        members
+            .iter()
+            .map(|dirs| dirs.iter().map(PathBuf::from).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_path_components() {
+        let members = member_dirs(&[&["/p/crates/foo"], &["/p/crates/foo-bar"]]);
+        assert_eq!(
+            Some(0),
+            owning_member(Path::new("/p/crates/foo/src/lib.rs"), &members)
+        );
+        assert_eq!(
+            Some(1),
+            owning_member(Path::new("/p/crates/foo-bar/src/lib.rs"), &members)
+        );
+        assert_eq!(
+            None,
+            owning_member(Path::new("/p/crates/foobar/src/lib.rs"), &members)
+        );
+    }
+
+    #[test]
+    fn test_nested_members() {
+        let members = member_dirs(&[&["/p"], &["/p/crates/foo"], &["/p/crates/foo/bar"]]);
+        assert_eq!(
+            Some(0),
+            owning_member(Path::new("/p/src/main.rs"), &members)
+        );
+        assert_eq!(
+            Some(1),
+            owning_member(Path::new("/p/crates/foo/src/lib.rs"), &members)
+        );
+        assert_eq!(
+            Some(2),
+            owning_member(Path::new("/p/crates/foo/bar/src/lib.rs"), &members)
+        );
+    }
+
+    #[test]
+    fn test_target_sources() {
+        // `foo` has a target living outside of its directory
+        let members = member_dirs(&[&["/p"], &["/p/crates/foo", "/p/shared/foo"]]);
+        assert_eq!(
+            Some(1),
+            owning_member(Path::new("/p/shared/foo/lib.rs"), &members)
+        );
+        assert_eq!(
+            Some(0),
+            owning_member(Path::new("/p/shared/bar/lib.rs"), &members)
+        );
+    }
 }
 
 #[cfg(test)]
 mod intersections_tests {
     use crate::linter::{Lint, Location};
     use crate::vcs::Section;
+    use std::path::PathBuf;
 
     type TestSection = (&'static str, u32, u32);
     #[test]
@@ -298,8 +1089,19 @@ mod intersections_tests {
             (("foo.rs", 1, 10), ("foo.rs", 5, 12)),
             (("bar.rs", 1, 10), ("bar.rs", 5, 12)),
             (("foo/bar/baz.rs", 1, 10), ("foo/bar/baz.rs", 5, 12)),
-            (("foo\\bar\\baz.rs", 1, 10), ("foo/bar/baz.rs", 9, 12)),
             (("foo/1.rs", 1, 10), ("foo/1.rs", 5, 12)),
+            (("./foo/bar/baz.rs", 1, 10), ("foo/bar/baz.rs", 5, 12)),
+            (("foo/qux/../bar/baz.rs", 1, 10), ("foo/bar/baz.rs", 5, 12)),
+        ];
+        assert_all_files_match(files_to_test);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_files_match_windows_separators() {
+        let files_to_test = vec![
+            (("foo\\bar\\baz.rs", 1, 10), ("foo/bar/baz.rs", 9, 12)),
+            (("foo\\\\bar\\baz.rs", 1, 10), ("foo/bar/baz.rs", 9, 12)),
         ];
         assert_all_files_match(files_to_test);
     }
@@ -311,8 +1113,19 @@ mod intersections_tests {
             (("bar.rs", 1, 10), ("baz.rs", 5, 12)),
             (("bar.rs", 1, 10), ("bar.js", 5, 12)),
             (("foo/bar/baz.rs", 1, 10), ("/foo/bar/baz.rs", 5, 12)),
-            (("foo\\\\bar\\baz.rs", 1, 10), ("foo/bar/baz.rs", 9, 12)),
             (("foo/1.rs", 1, 10), ("foo/2.rs", 5, 12)),
+            (("../foo/bar/baz.rs", 1, 10), ("foo/bar/baz.rs", 5, 12)),
+        ];
+        assert_no_files_match(files_to_test);
+    }
+
+    // Backslashes are not separators outside of Windows
+    #[cfg(not(windows))]
+    #[test]
+    fn test_files_dont_match_backslashes() {
+        let files_to_test = vec![
+            (("foo\\bar\\baz.rs", 1, 10), ("foo/bar/baz.rs", 9, 12)),
+            (("foo\\\\bar\\baz.rs", 1, 10), ("foo/bar/baz.rs", 9, 12)),
         ];
         assert_no_files_match(files_to_test);
     }
@@ -349,12 +1162,13 @@ mod intersections_tests {
             let lint = Lint {
                 message: String::new(),
                 location: Location {
-                    path: String::from(lint_section.0),
+                    path: PathBuf::from(lint_section.0),
                     lines: [lint_section.1, lint_section.2],
                 },
+                ..Lint::default()
             };
             let git = Section {
-                file_name: String::from(git_section.0),
+                file_name: PathBuf::from(git_section.0),
                 line_start: git_section.1,
                 line_end: git_section.2,
             };
@@ -376,12 +1190,13 @@ mod intersections_tests {
             let lint = Lint {
                 message: String::new(),
                 location: Location {
-                    path: String::from(lint_section.0),
+                    path: PathBuf::from(lint_section.0),
                     lines: [lint_section.1, lint_section.2],
                 },
+                ..Lint::default()
             };
             let git = Section {
-                file_name: String::from(git_section.0),
+                file_name: PathBuf::from(git_section.0),
                 line_start: git_section.1,
                 line_end: git_section.2,
             };
@@ -428,13 +1243,14 @@ mod intersections_tests {
         let lint = Lint {
             message: String::new(),
             location: Location {
-                path: String::from(lint_section.0),
+                path: PathBuf::from(lint_section.0),
                 lines: [lint_section.1, lint_section.2],
             },
+            ..Lint::default()
         };
 
         let git_section = Section {
-            file_name: String::from(git_section.0),
+            file_name: PathBuf::from(git_section.0),
             line_start: git_section.1,
             line_end: git_section.2,
         };
@@ -1,8 +1,205 @@
-use crate::error::Error;
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 
-pub fn get_absolute_file_path(file_path: impl AsRef<Path>) -> Result<String, Error> {
-    let mut absolute_path = std::env::current_dir()?;
-    absolute_path.push(file_path);
-    Ok(absolute_path.to_string_lossy().to_string())
+/// Lexically normalizes `path`: `.` components are dropped,
+/// and `..` components remove the component preceding them.
+///
+/// The file system is not touched, so symbolic links are not resolved.
+pub fn normalize_path(path: impl AsRef<Path>) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.as_ref().components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                // There is nothing above the root
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => normalized.push(".."),
+            },
+            c => normalized.push(c),
+        }
+    }
+    normalized
+}
+
+/// Resolves `path`, either absolute or relative to `root`,
+/// into a path relative to `root`.
+///
+/// Symbolic links are resolved for existing paths.
+/// Paths outside of `root` are returned as absolute paths.
+pub fn relative_path(root: impl AsRef<Path>, path: impl AsRef<Path>) -> PathBuf {
+    let root = canonical_path(root);
+    let path = canonical_path(root.join(path));
+    match path.strip_prefix(&root) {
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => path,
+    }
+}
+
+/// Returns true if both paths point to the same location once normalized.
+pub fn paths_match(a: impl AsRef<Path>, b: impl AsRef<Path>) -> bool {
+    normalize_path(a) == normalize_path(b)
+}
+
+/// Applies `f` to each item, on up to `jobs` threads.
+///
+/// The results are returned in the order of the items.
+pub fn parallel_map<T, R, F>(jobs: usize, items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<R>>> = Mutex::new(items.iter().map(|_| None).collect());
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, items.len().max(1)) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                let Some(item) = items.get(i) else {
+                    break;
+                };
+                let result = f(item);
+                results.lock().expect("a worker thread panicked")[i] = Some(result);
+            });
+        }
+    });
+    results
+        .into_inner()
+        .expect("a worker thread panicked")
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+fn canonical_path(path: impl AsRef<Path>) -> PathBuf {
+    std::fs::canonicalize(&path).unwrap_or_else(|_| normalize_path(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_path, parallel_map, paths_match, relative_path};
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Barrier, Condvar, Mutex};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_normalize_path() {
+        let paths = vec![
+            ("foo/bar.rs", "foo/bar.rs"),
+            ("./foo/bar.rs", "foo/bar.rs"),
+            ("foo/./bar.rs", "foo/bar.rs"),
+            ("foo//bar.rs", "foo/bar.rs"),
+            ("foo/baz/../bar.rs", "foo/bar.rs"),
+            ("../foo/bar.rs", "../foo/bar.rs"),
+            ("foo/../../bar.rs", "../bar.rs"),
+            ("/foo/../../bar.rs", "/bar.rs"),
+        ];
+        for (path, expected) in paths {
+            assert_eq!(PathBuf::from(expected), normalize_path(path), "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_paths_match() {
+        assert!(paths_match("foo/bar.rs", "./foo/bar.rs"));
+        assert!(paths_match("foo/baz/../bar.rs", "foo/bar.rs"));
+        assert!(!paths_match("foo/bar.rs", "/foo/bar.rs"));
+        assert!(!paths_match("foo/bar.rs", "foo/baz.rs"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_paths_match_separators() {
+        assert!(paths_match("foo\\bar\\baz.rs", "foo/bar/baz.rs"));
+    }
+
+    #[test]
+    fn test_parallel_map_order() {
+        let items: Vec<usize> = (0..4).collect();
+        let started = Barrier::new(items.len());
+        let finished = Mutex::new(0);
+        let finished_changed = Condvar::new();
+        let results = parallel_map(4, &items, |i| {
+            started.wait();
+            // Later items finish first
+            let mut finished = finished_changed
+                .wait_while(finished.lock().unwrap(), |f| *f != 3 - i)
+                .unwrap();
+            *finished += 1;
+            finished_changed.notify_all();
+            i * 2
+        });
+        assert_eq!(vec![0, 2, 4, 6], results);
+        assert!(parallel_map(4, &Vec::<u64>::new(), |i| *i).is_empty());
+    }
+
+    #[test]
+    fn test_parallel_map_jobs() {
+        let running = AtomicUsize::new(0);
+        let max_running = AtomicUsize::new(0);
+        // Both jobs are running the items two by two
+        let both_running = Barrier::new(2);
+        let items: Vec<u64> = (0..6).collect();
+        parallel_map(2, &items, |_| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            max_running.fetch_max(now, Ordering::SeqCst);
+            both_running.wait();
+            running.fetch_sub(1, Ordering::SeqCst);
+        });
+        assert_eq!(2, max_running.load(Ordering::SeqCst));
+        // 0 jobs still makes progress
+        assert_eq!(vec![1], parallel_map(0, &[1], |i| *i));
+    }
+
+    #[test]
+    fn test_relative_path() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("foo")).unwrap();
+        fs::write(root.join("foo/bar.rs"), "").unwrap();
+
+        assert_eq!(
+            PathBuf::from("foo/bar.rs"),
+            relative_path(root, "foo/bar.rs")
+        );
+        assert_eq!(
+            PathBuf::from("foo/bar.rs"),
+            relative_path(root, root.join("foo/../foo/bar.rs"))
+        );
+        // The file doesn't have to exist
+        assert_eq!(
+            PathBuf::from("foo/baz.rs"),
+            relative_path(root, "foo/./baz.rs")
+        );
+        assert_eq!(
+            PathBuf::from("/elsewhere/bar.rs"),
+            relative_path(root, "/elsewhere/bar.rs")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_relative_path_symlink() {
+        let dir = TempDir::new().unwrap();
+        let checkout = dir.path().join("checkout");
+        fs::create_dir_all(checkout.join("src")).unwrap();
+        fs::write(checkout.join("src/lib.rs"), "").unwrap();
+        let link = dir.path().join("link");
+        std::os::unix::fs::symlink(&checkout, &link).unwrap();
+
+        assert_eq!(
+            PathBuf::from("src/lib.rs"),
+            relative_path(&checkout, link.join("src/lib.rs"))
+        );
+        assert_eq!(
+            PathBuf::from("src/lib.rs"),
+            relative_path(&link, checkout.join("src/lib.rs"))
+        );
+    }
 }
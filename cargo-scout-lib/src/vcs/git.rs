@@ -1,13 +1,112 @@
 use super::{Section, VCS};
 use crate::error::Error;
-use crate::utils::get_absolute_file_path;
 use cargo_scout_macros::info;
 use colored::Colorize;
-use git2::{Delta, DiffOptions, Repository};
-use std::path::Path;
+use git2::{AttrCheckFlags, Delta, DiffOptions, ErrorCode, Oid, Repository, Sort, Tree};
+use std::collections::{BTreeSet, HashMap};
+use std::convert::TryFrom;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 pub struct Git {
     target_branch: String,
+    author: Option<Author>,
+    time_window: Option<TimeWindow>,
+}
+
+/// The author whose lines will be kept.
+///
+/// Lines that have not been committed yet
+/// are considered to be the current user's.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Author {
+    /// The author configured in git's `user.email`
+    CurrentUser,
+    /// The author with this email address
+    Email(String),
+}
+
+/// The lines to check when no target branch is relevant,
+/// for example in long-running refactoring campaigns.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimeWindow {
+    /// Lines last changed after this unix timestamp, according to git blame
+    Since(i64),
+    /// Lines changed by the last N commits
+    LastCommits(usize),
+}
+
+impl TimeWindow {
+    /// Parses a date, either absolute (`2020-01-31`)
+    /// or relative to now (`3 days`, `2 weeks ago`).
+    ///
+    /// # Example
+    /// ```
+    /// # use cargo_scout_lib::vcs::git::TimeWindow;
+    /// assert_eq!(TimeWindow::Since(1_580_428_800), TimeWindow::since("2020-01-31")?);
+    /// # Ok::<(), cargo_scout_lib::Error>(())
+    /// ```
+    #[allow(clippy::missing_errors_doc)]
+    pub fn since(date: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidDate(date.to_string());
+        let date = date.trim();
+        let relative = date.strip_suffix(" ago").unwrap_or(date);
+        let mut words = relative.split_whitespace();
+        if let (Some(count), Some(unit), None) = (words.next(), words.next(), words.next()) {
+            let count: i64 = count.parse().map_err(|_| invalid())?;
+            let unit = match unit.trim_end_matches('s') {
+                "day" => 24 * 60 * 60,
+                "week" => 7 * 24 * 60 * 60,
+                _ => return Err(invalid()),
+            };
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_err(|_| invalid())?
+                .as_secs();
+            let now = i64::try_from(now).map_err(|_| invalid())?;
+            return count
+                .checked_mul(unit)
+                .and_then(|ago| now.checked_sub(ago))
+                .map(Self::Since)
+                .ok_or_else(invalid);
+        }
+
+        let parts: Vec<i64> = date
+            .split('-')
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map_err(|_| invalid())?;
+        match parts.as_slice() {
+            &[year, month, day]
+                if (1..=12).contains(&month) && (1..=days_in_month(year, month)).contains(&day) =>
+            {
+                Ok(Self::Since(
+                    days_from_civil(year, month, day) * 24 * 60 * 60,
+                ))
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Number of days since 1970-01-01 in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
 }
 
 impl Default for Git {
@@ -15,6 +114,8 @@ impl Default for Git {
     fn default() -> Self {
         Self {
             target_branch: "HEAD".to_string(),
+            author: None,
+            time_window: None,
         }
     }
 }
@@ -22,7 +123,22 @@ impl Default for Git {
 impl Git {
     #[must_use]
     pub fn with_target(target_branch: String) -> Self {
-        Self { target_branch }
+        Self {
+            target_branch,
+            ..Self::default()
+        }
+    }
+
+    /// Only keep the changed lines written by `author`, according to git blame.
+    pub fn set_author(&mut self, author: Option<Author>) -> &mut Self {
+        self.author = author;
+        self
+    }
+
+    /// Check the lines changed in a time window, instead of the diff with the target branch.
+    pub fn set_time_window(&mut self, time_window: Option<TimeWindow>) -> &mut Self {
+        self.time_window = time_window;
+        self
     }
 }
 
@@ -31,49 +147,343 @@ impl VCS for Git {
     where
         P: AsRef<Path>,
     {
-        info!("[VCS] - Getting diff with target {}", &self.target_branch);
         let repo = Repository::discover(repo_path)?;
-        let tree = repo.revparse_single(&self.target_branch)?.peel_to_tree()?;
-        let mut config = DiffOptions::default();
-        config
-            .context_lines(0)
-            .show_untracked_content(true)
-            .recurse_untracked_dirs(true);
-        let diff = repo.diff_tree_to_workdir_with_index(Some(&tree), Some(&mut config))?;
-        let mut sections = Vec::new();
-        diff.foreach(
-            &mut |_delta, _progress| true,
-            None,
-            Some(&mut |delta, hunk| {
-                match delta.status() {
-                    Delta::Modified | Delta::Added | Delta::Untracked => {
-                        if let Some(file_path) = delta.new_file().path() {
-                            // Path returns the path of the entry relative to the working directory.
-                            // We can get the absolute path
-                            if let Ok(file_name) = get_absolute_file_path(&file_path) {
-                                if file_name.ends_with(".rs") {
-                                    sections.push(Section {
-                                        file_name,
-                                        line_start: hunk.new_start(),
-                                        line_end: hunk.new_start() + hunk.new_lines(),
-                                    });
-                                }
-                            }
+        let mut sections = match &self.time_window {
+            None => {
+                info!("[VCS] - Getting diff with target {}", &self.target_branch);
+                let tree = repo
+                    .revparse_single(&self.target_branch)
+                    .map_err(|e| match e.code() {
+                        ErrorCode::NotFound | ErrorCode::InvalidSpec | ErrorCode::Ambiguous => {
+                            Error::InvalidRevision(self.target_branch.clone())
+                        }
+                        _ => Error::Git(e),
+                    })?
+                    .peel_to_tree()?;
+                diff_sections(&repo, Some(&tree))?
+            }
+            Some(TimeWindow::LastCommits(count)) => {
+                info!(
+                    "[VCS] - Getting lines changed in the last {} commit(s)",
+                    count
+                );
+                let tree = match repo.revparse_single(&format!("HEAD~{}", count)) {
+                    Ok(base) => Some(base.peel_to_tree()?),
+                    // The history is shorter than `count`, everything has changed
+                    Err(e) if e.code() == ErrorCode::NotFound => None,
+                    Err(e) => return Err(e.into()),
+                };
+                diff_sections(&repo, tree.as_ref())?
+            }
+            Some(TimeWindow::Since(since)) => {
+                info!("[VCS] - Getting lines changed since timestamp {}", since);
+                sections_since(&repo, *since)?
+            }
+        };
+        if let Some(author) = &self.author {
+            sections = sections_by_author(&repo, sections, author)?;
+        }
+        Ok(sections)
+    }
+
+    // Sections are relative to the working directory of the repository,
+    // no matter which directory we have been called from.
+    fn root<P>(&self, repo_path: P) -> Result<PathBuf, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let repo = Repository::discover(repo_path)?;
+        Ok(std::fs::canonicalize(workdir(&repo)?)?)
+    }
+}
+
+fn workdir(repo: &Repository) -> Result<&Path, Error> {
+    repo.workdir()
+        .ok_or_else(|| git2::Error::from_str("cannot diff against a bare repository").into())
+}
+
+// Sections of the diff between `tree` and the working directory.
+// Paths are relative to the working directory.
+fn diff_sections(repo: &Repository, tree: Option<&Tree>) -> Result<Vec<Section>, Error> {
+    let mut config = DiffOptions::default();
+    config
+        .context_lines(0)
+        .show_untracked_content(true)
+        .recurse_untracked_dirs(true);
+    let diff = repo.diff_tree_to_workdir_with_index(tree, Some(&mut config))?;
+    let mut sections = Vec::new();
+    diff.foreach(
+        &mut |_delta, _progress| true,
+        None,
+        Some(&mut |delta, hunk| {
+            match delta.status() {
+                Delta::Modified | Delta::Added | Delta::Untracked => {
+                    if let Some(file_path) = delta.new_file().path() {
+                        if is_generated_or_vendored(repo, file_path) {
+                            return true;
+                        }
+                        // Path returns the path of the entry relative to the working directory.
+                        if is_rust_file(file_path) {
+                            sections.push(Section {
+                                file_name: file_path.to_path_buf(),
+                                line_start: hunk.new_start(),
+                                line_end: hunk.new_start() + hunk.new_lines(),
+                            });
                         }
                     }
-                    _ => {}
                 }
-                true
-            }),
-            None,
-        )?;
-        Ok(sections)
+                _ => {}
+            }
+            true
+        }),
+        None,
+    )?;
+    Ok(sections)
+}
+
+// Sections of the lines last changed after `since`, or not committed yet.
+fn sections_since(repo: &Repository, since: i64) -> Result<Vec<Section>, Error> {
+    let head = repo.head()?.peel_to_tree()?;
+    // Files with uncommitted changes
+    let mut files: BTreeSet<PathBuf> = diff_sections(repo, Some(&head))?
+        .into_iter()
+        .map(|section| section.file_name)
+        .collect();
+    // Files changed by recent commits
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    revwalk.set_sorting(Sort::TIME);
+    for id in revwalk {
+        let commit = repo.find_commit(id?)?;
+        // Like `git log --since`, the walk stops at the first older commit,
+        // the commits being sorted by time.
+        if commit.time().seconds() < since {
+            break;
+        }
+        let parent_tree = match commit.parents().next() {
+            Some(parent) => Some(parent.tree()?),
+            None => None,
+        };
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+        for delta in diff.deltas() {
+            if let (Delta::Modified | Delta::Added, Some(path)) =
+                (delta.status(), delta.new_file().path())
+            {
+                if is_rust_file(path) && !is_generated_or_vendored(repo, path) {
+                    files.insert(path.to_path_buf());
+                }
+            }
+        }
+    }
+
+    let mut sections = Vec::new();
+    for file in files {
+        // The file may have been deleted since
+        let origins = match line_origins(repo, &file) {
+            Ok(origins) => origins,
+            Err(Error::Io(e)) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        let whole_file = Section {
+            file_name: file,
+            line_start: 1,
+            line_end: u32::try_from(origins.len() + 1).unwrap_or(u32::MAX),
+        };
+        let is_recent = |line: u32| match origins.get(line as usize - 1) {
+            Some(LineOrigin::Committed { time, .. }) => *time >= since,
+            Some(LineOrigin::Uncommitted) | None => true,
+        };
+        sections.extend(split_section(&whole_file, is_recent));
+    }
+    Ok(sections)
+}
+
+/// Where a line of a file in the working directory comes from.
+#[derive(Debug, Clone, PartialEq)]
+enum LineOrigin {
+    /// The line has not been committed yet
+    Uncommitted,
+    /// The line was last changed by a commit from `email`, at `time`
+    Committed { email: String, time: i64 },
+}
+
+// Sections are split so they only contain lines written by `author`.
+fn sections_by_author(
+    repo: &Repository,
+    sections: Vec<Section>,
+    author: &Author,
+) -> Result<Vec<Section>, Error> {
+    let config = repo.config()?;
+    let email = match author {
+        Author::CurrentUser => config.get_string("user.email")?,
+        Author::Email(email) => email.clone(),
+    };
+    info!("[VCS] - Keeping lines written by {}", &email);
+    let uncommitted_are_authors = config
+        .get_string("user.email")
+        .is_ok_and(|current_user| current_user.eq_ignore_ascii_case(&email));
+
+    let mut origins: HashMap<PathBuf, Vec<LineOrigin>> = HashMap::new();
+    let mut authored_sections = Vec::new();
+    for section in sections {
+        if !origins.contains_key(&section.file_name) {
+            let file_origins = line_origins(repo, &section.file_name)?;
+            origins.insert(section.file_name.clone(), file_origins);
+        }
+        let file_origins = &origins[&section.file_name];
+        let is_authors = |line: u32| match file_origins.get(line as usize - 1) {
+            Some(LineOrigin::Committed { email: e, .. }) => e.eq_ignore_ascii_case(&email),
+            Some(LineOrigin::Uncommitted) | None => uncommitted_are_authors,
+        };
+        authored_sections.extend(split_section(&section, is_authors));
+    }
+    Ok(authored_sections)
+}
+
+// Splits a section into the runs of consecutive lines that match `keep`.
+// A section without lines (a deletion) is kept if its first line matches.
+fn split_section(section: &Section, keep: impl Fn(u32) -> bool) -> Vec<Section> {
+    if section.line_end <= section.line_start {
+        return if keep(section.line_start.max(1)) {
+            vec![section.clone()]
+        } else {
+            Vec::new()
+        };
+    }
+    let mut split = Vec::new();
+    let mut start = None;
+    for line in section.line_start..section.line_end {
+        match (keep(line), start) {
+            (true, None) => start = Some(line),
+            (false, Some(s)) => {
+                split.push(Section {
+                    file_name: section.file_name.clone(),
+                    line_start: s,
+                    line_end: line,
+                });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        split.push(Section {
+            file_name: section.file_name.clone(),
+            line_start: s,
+            line_end: section.line_end,
+        });
+    }
+    split
+}
+
+// Blames the file at `path` (relative to the working directory),
+// and maps the result onto the lines of the working directory file.
+fn line_origins(repo: &Repository, path: &Path) -> Result<Vec<LineOrigin>, Error> {
+    let line_count = std::fs::read_to_string(workdir(repo)?.join(path))?
+        .lines()
+        .count();
+    let head = repo.head()?.peel_to_tree()?;
+    if head.get_path(path).is_err() {
+        // The file has never been committed
+        return Ok(vec![LineOrigin::Uncommitted; line_count]);
+    }
+    let blame = repo.blame_file(path, None)?;
+
+    // Lines changed since HEAD are not part of the blame
+    let mut config = DiffOptions::default();
+    config.context_lines(0).pathspec(path);
+    let diff = repo.diff_tree_to_workdir(Some(&head), Some(&mut config))?;
+    let mut hunks = Vec::new();
+    diff.foreach(
+        &mut |_delta, _progress| true,
+        None,
+        Some(&mut |_delta, hunk| {
+            hunks.push((
+                hunk.old_lines() as usize,
+                hunk.new_start() as usize,
+                hunk.new_lines() as usize,
+            ));
+            true
+        }),
+        None,
+    )?;
+
+    let mut commit_times: HashMap<Oid, i64> = HashMap::new();
+    let mut origins = Vec::with_capacity(line_count);
+    for line in 1..=line_count {
+        let mut uncommitted = false;
+        // Lines removed and added since HEAD, before the current line
+        let (mut removed, mut added) = (0, 0);
+        for &(old_lines, new_start, new_lines) in &hunks {
+            if line >= new_start && line < new_start + new_lines {
+                uncommitted = true;
+                break;
+            }
+            // A deletion hunk starts at the line preceding the deleted lines
+            if line >= new_start + new_lines.max(1) {
+                removed += old_lines;
+                added += new_lines;
+            }
+        }
+        let origin = if uncommitted {
+            LineOrigin::Uncommitted
+        } else {
+            match blame.get_line(line + removed - added) {
+                Some(hunk) if !hunk.final_commit_id().is_zero() => {
+                    let id = hunk.final_commit_id();
+                    let time = if let Some(time) = commit_times.get(&id) {
+                        *time
+                    } else {
+                        let time = repo.find_commit(id)?.time().seconds();
+                        commit_times.insert(id, time);
+                        time
+                    };
+                    LineOrigin::Committed {
+                        email: hunk
+                            .final_signature()
+                            .email()
+                            .unwrap_or_default()
+                            .to_string(),
+                        time,
+                    }
+                }
+                _ => LineOrigin::Uncommitted,
+            }
+        };
+        origins.push(origin);
+    }
+    Ok(origins)
+}
+
+fn is_rust_file(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "rs")
+}
+
+// Files marked as `linguist-generated` or `linguist-vendored`
+// in `.gitattributes` should not be linted.
+fn is_generated_or_vendored(repo: &Repository, path: &Path) -> bool {
+    ["linguist-generated", "linguist-vendored"]
+        .iter()
+        .any(|attr| {
+            repo.get_attr(path, attr, AttrCheckFlags::FILE_THEN_INDEX)
+                .is_ok_and(attr_is_set)
+        })
+}
+
+// libgit2 returns a magic string for attributes set without a value (`attr`),
+// and the raw value for attributes set with one (`attr=true`).
+fn attr_is_set(value: Option<&str>) -> bool {
+    match value {
+        Some(v) => v == "true" || v == "[internal]__TRUE__",
+        None => false,
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{get_absolute_file_path, Error, Git, Path, Repository, Section, VCS};
+    use super::{Author, Error, Git, Path, PathBuf, Repository, Section, TimeWindow, VCS};
+    use git2::{Signature, Time};
+    use std::convert::TryFrom;
     use std::fs::{self, File};
     use std::io::Write;
     use tempfile::TempDir;
@@ -98,12 +508,12 @@ mod tests {
 
         let expected = vec![
             Section {
-                file_name: get_absolute_file_path(&"bar.rs")?,
+                file_name: PathBuf::from("bar.rs"),
                 line_start: 1,
                 line_end: 5,
             },
             Section {
-                file_name: get_absolute_file_path(&"foo.rs")?,
+                file_name: PathBuf::from("foo.rs"),
                 line_start: 1,
                 line_end: 7,
             },
@@ -123,12 +533,12 @@ mod tests {
 
         let expected = vec![
             Section {
-                file_name: get_absolute_file_path(&"foo.rs")?,
+                file_name: PathBuf::from("foo.rs"),
                 line_start: 1,
                 line_end: 7,
             },
             Section {
-                file_name: get_absolute_file_path(&"inside/some/dir/bar.rs")?,
+                file_name: PathBuf::from("inside/some/dir/bar.rs"),
                 line_start: 1,
                 line_end: 5,
             },
@@ -140,6 +550,44 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn from_subdirectory() -> Result<()> {
+        let repo = RepoFixture::new()?
+            .write("foo.rs", "test_files/git/added/foo.rs")?
+            .write("inside/some/dir/bar.rs", "test_files/git/added/bar.rs")?;
+
+        // Paths are anchored on the repository, not on the directory we were called from
+        let expected = vec![
+            Section {
+                file_name: PathBuf::from("foo.rs"),
+                line_start: 1,
+                line_end: 7,
+            },
+            Section {
+                file_name: PathBuf::from("inside/some/dir/bar.rs"),
+                line_start: 1,
+                line_end: 5,
+            },
+        ];
+
+        let git = Git::default();
+        let actual = git.sections(repo.path().join("inside/some"))?;
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+
+    #[test]
+    fn root() -> Result<()> {
+        let repo =
+            RepoFixture::new()?.write("inside/some/dir/bar.rs", "test_files/git/added/bar.rs")?;
+
+        let git = Git::default();
+        let expected = std::fs::canonicalize(repo.path())?;
+        assert_eq!(expected, git.root(repo.path())?);
+        assert_eq!(expected, git.root(repo.path().join("inside/some"))?);
+        Ok(())
+    }
+
     #[test]
     fn modified_files() -> Result<()> {
         let files = &["foo.rs", "bar.rs"];
@@ -153,22 +601,22 @@ mod tests {
 
         let expected = vec![
             Section {
-                file_name: get_absolute_file_path(&"bar.rs")?,
+                file_name: PathBuf::from("bar.rs"),
                 line_start: 1,
                 line_end: 2,
             },
             Section {
-                file_name: get_absolute_file_path(&"bar.rs")?,
+                file_name: PathBuf::from("bar.rs"),
                 line_start: 5,
                 line_end: 9,
             },
             Section {
-                file_name: get_absolute_file_path(&"foo.rs")?,
+                file_name: PathBuf::from("foo.rs"),
                 line_start: 3,
                 line_end: 4,
             },
             Section {
-                file_name: get_absolute_file_path(&"foo.rs")?,
+                file_name: PathBuf::from("foo.rs"),
                 line_start: 6,
                 line_end: 7,
             },
@@ -188,7 +636,7 @@ mod tests {
             .stage(&["foo.rs", "bar.txt"])?;
 
         let expected = vec![Section {
-            file_name: get_absolute_file_path(&"foo.rs")?,
+            file_name: PathBuf::from("foo.rs"),
             line_start: 1,
             line_end: 7,
         }];
@@ -199,6 +647,34 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn generated_and_vendored_files() -> Result<()> {
+        let repo = RepoFixture::new()?
+            .write(".gitattributes", "test_files/git/attributes/gitattributes")?
+            .write("foo.rs", "test_files/git/added/foo.rs")?
+            .write("generated/bar.rs", "test_files/git/added/bar.rs")?
+            .write("vendor/bar.rs", "test_files/git/added/bar.rs")?
+            .write("not_generated.rs", "test_files/git/added/bar.rs")?;
+
+        let expected = vec![
+            Section {
+                file_name: PathBuf::from("foo.rs"),
+                line_start: 1,
+                line_end: 7,
+            },
+            Section {
+                file_name: PathBuf::from("not_generated.rs"),
+                line_start: 1,
+                line_end: 5,
+            },
+        ];
+
+        let git = Git::default();
+        let actual = git.sections(repo.path())?;
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+
     #[test]
     fn other_branch() -> Result<()> {
         let repo = RepoFixture::new()?
@@ -210,12 +686,12 @@ mod tests {
 
         let expected = vec![
             Section {
-                file_name: get_absolute_file_path(&"foo.rs")?,
+                file_name: PathBuf::from("foo.rs"),
                 line_start: 3,
                 line_end: 4,
             },
             Section {
-                file_name: get_absolute_file_path(&"foo.rs")?,
+                file_name: PathBuf::from("foo.rs"),
                 line_start: 6,
                 line_end: 7,
             },
@@ -224,6 +700,257 @@ mod tests {
         let git = Git::with_target("other".to_string());
         let actual = git.sections(repo.path())?;
         assert_eq!(expected, actual);
+
+        let git = Git::with_target("unknown".to_string());
+        assert!(matches!(
+            git.sections(repo.path()),
+            Err(Error::InvalidRevision(target)) if target == "unknown"
+        ));
+        Ok(())
+    }
+
+    fn author_fixture() -> Result<RepoFixture> {
+        RepoFixture::new()?
+            .branch("base")?
+            .set_email("alice@example.com")?
+            .write("foo.rs", "test_files/git/modified/old/foo.rs")?
+            .commit("master", &["foo.rs"])?
+            .set_email("bob@example.com")?
+            .write("foo.rs", "test_files/git/modified/new/foo.rs")?
+            .write("bar.rs", "test_files/git/added/bar.rs")
+    }
+
+    #[test]
+    fn author_current_user() -> Result<()> {
+        let repo = author_fixture()?;
+
+        // Uncommitted lines belong to the current user
+        let expected = vec![
+            Section {
+                file_name: PathBuf::from("bar.rs"),
+                line_start: 1,
+                line_end: 5,
+            },
+            Section {
+                file_name: PathBuf::from("foo.rs"),
+                line_start: 3,
+                line_end: 4,
+            },
+            Section {
+                file_name: PathBuf::from("foo.rs"),
+                line_start: 6,
+                line_end: 7,
+            },
+        ];
+
+        let mut git = Git::with_target("base".to_string());
+        git.set_author(Some(Author::CurrentUser));
+        let actual = git.sections(repo.path())?;
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+
+    #[test]
+    fn author_email() -> Result<()> {
+        let repo = author_fixture()?;
+
+        let expected = vec![
+            Section {
+                file_name: PathBuf::from("foo.rs"),
+                line_start: 1,
+                line_end: 3,
+            },
+            Section {
+                file_name: PathBuf::from("foo.rs"),
+                line_start: 4,
+                line_end: 6,
+            },
+            Section {
+                file_name: PathBuf::from("foo.rs"),
+                line_start: 7,
+                line_end: 8,
+            },
+        ];
+
+        let mut git = Git::with_target("base".to_string());
+        git.set_author(Some(Author::Email("Alice@example.com".to_string())));
+        let actual = git.sections(repo.path())?;
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+
+    fn time_window_fixture() -> Result<RepoFixture> {
+        RepoFixture::new()?
+            .write("foo.rs", "test_files/git/modified/old/foo.rs")?
+            .write("bar.rs", "test_files/git/modified/old/bar.rs")?
+            .commit_at("master", &["foo.rs", "bar.rs"], 1_000_000)?
+            .write("foo.rs", "test_files/git/modified/new/foo.rs")?
+            .commit_at("master", &["foo.rs"], 2_000_000)
+    }
+
+    #[test]
+    fn time_window_since() -> Result<()> {
+        let repo = time_window_fixture()?.write("bar.rs", "test_files/git/modified/new/bar.rs")?;
+
+        // Uncommitted lines are always recent
+        let expected = vec![
+            Section {
+                file_name: PathBuf::from("bar.rs"),
+                line_start: 1,
+                line_end: 2,
+            },
+            Section {
+                file_name: PathBuf::from("bar.rs"),
+                line_start: 5,
+                line_end: 9,
+            },
+            Section {
+                file_name: PathBuf::from("foo.rs"),
+                line_start: 3,
+                line_end: 4,
+            },
+            Section {
+                file_name: PathBuf::from("foo.rs"),
+                line_start: 6,
+                line_end: 7,
+            },
+        ];
+
+        let mut git = Git::default();
+        git.set_time_window(Some(TimeWindow::Since(1_500_000)));
+        let actual = git.sections(repo.path())?;
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+
+    #[test]
+    fn time_window_since_skewed_clock() -> Result<()> {
+        // The last commit is dated before the one preceding it
+        let repo = RepoFixture::new()?
+            .write("foo.rs", "test_files/git/modified/old/foo.rs")?
+            .write("bar.rs", "test_files/git/modified/old/bar.rs")?
+            .commit_at("master", &["foo.rs", "bar.rs"], 2_000_000)?
+            .write("foo.rs", "test_files/git/modified/new/foo.rs")?
+            .commit_at("master", &["foo.rs"], 1_000_000)?;
+
+        // Only the lines of the first commit are recent
+        let expected = vec![
+            Section {
+                file_name: PathBuf::from("bar.rs"),
+                line_start: 1,
+                line_end: 5,
+            },
+            Section {
+                file_name: PathBuf::from("foo.rs"),
+                line_start: 1,
+                line_end: 3,
+            },
+            Section {
+                file_name: PathBuf::from("foo.rs"),
+                line_start: 4,
+                line_end: 6,
+            },
+            Section {
+                file_name: PathBuf::from("foo.rs"),
+                line_start: 7,
+                line_end: 8,
+            },
+        ];
+
+        let mut git = Git::default();
+        git.set_time_window(Some(TimeWindow::Since(1_500_000)));
+        let actual = git.sections(repo.path())?;
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+
+    #[test]
+    fn time_window_last_commits() -> Result<()> {
+        let repo = time_window_fixture()?;
+
+        let expected = vec![
+            Section {
+                file_name: PathBuf::from("foo.rs"),
+                line_start: 3,
+                line_end: 4,
+            },
+            Section {
+                file_name: PathBuf::from("foo.rs"),
+                line_start: 6,
+                line_end: 7,
+            },
+        ];
+
+        let mut git = Git::default();
+        git.set_time_window(Some(TimeWindow::LastCommits(1)));
+        let actual = git.sections(repo.path())?;
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+
+    #[test]
+    fn time_window_more_commits_than_history() -> Result<()> {
+        let repo = time_window_fixture()?;
+
+        let expected = vec![
+            Section {
+                file_name: PathBuf::from("bar.rs"),
+                line_start: 1,
+                line_end: 5,
+            },
+            Section {
+                file_name: PathBuf::from("foo.rs"),
+                line_start: 1,
+                line_end: 8,
+            },
+        ];
+
+        let mut git = Git::default();
+        git.set_time_window(Some(TimeWindow::LastCommits(10)));
+        let actual = git.sections(repo.path())?;
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+
+    #[test]
+    fn time_window_parse_since() -> Result<()> {
+        assert_eq!(TimeWindow::Since(0), TimeWindow::since("1970-01-01")?);
+        assert_eq!(
+            TimeWindow::Since(1_580_428_800),
+            TimeWindow::since("2020-01-31")?
+        );
+        assert_eq!(
+            TimeWindow::Since(951_782_400),
+            TimeWindow::since("2000-02-29")?
+        );
+
+        let two_weeks = 14 * 24 * 60 * 60;
+        for date in &["2 weeks", "2 weeks ago", "14 days", "14 days ago"] {
+            if let TimeWindow::Since(since) = TimeWindow::since(date)? {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs();
+                let now = i64::try_from(now).unwrap();
+                assert!((now - two_weeks - since).abs() < 60);
+            } else {
+                panic!("Expected TimeWindow::Since for {}", date);
+            }
+        }
+
+        for date in &[
+            "yesterday",
+            "2020-13-01",
+            "2020-01-32",
+            "2020-02-30",
+            "2019-02-29",
+            "2020-04-31",
+            "2 months",
+            "9999999999999999 days",
+            "2020/01/01",
+        ] {
+            assert!(TimeWindow::since(date).is_err());
+        }
         Ok(())
     }
 
@@ -291,6 +1018,28 @@ mod tests {
             Ok(self)
         }
 
+        pub fn commit_at(self, branch: &str, paths: &[&str], time: i64) -> Result<Self> {
+            {
+                let mut index = self.repo.index()?;
+                for path in paths {
+                    index.add_path(path.as_ref())?;
+                }
+                index.write()?;
+
+                let id = index.write_tree()?;
+                let tree = self.repo.find_tree(id)?;
+                let sig = Signature::new("name", "email", &Time::new(time, 0))?;
+
+                let target = self.repo.head()?.target().unwrap();
+                let parent = self.repo.find_commit(target)?;
+
+                let name = format!("refs/heads/{}", branch);
+                self.repo
+                    .commit(Some(&name), &sig, &sig, "some commit", &tree, &[&parent])?;
+            }
+            Ok(self)
+        }
+
         pub fn branch(self, name: &str) -> Result<Self> {
             {
                 let target = self.repo.head()?.target().unwrap();
@@ -301,6 +1050,11 @@ mod tests {
             Ok(self)
         }
 
+        pub fn set_email(self, email: &str) -> Result<Self> {
+            self.repo.config()?.set_str("user.email", email)?;
+            Ok(self)
+        }
+
         pub fn path(&self) -> &Path {
             self.dir.path()
         }
@@ -1,17 +1,29 @@
 pub mod git;
 use crate::error::Error;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub trait VCS {
     #[allow(clippy::missing_errors_doc)]
     fn sections<P>(&self, repo_path: P) -> Result<Vec<Section>, Error>
     where
         P: AsRef<Path>;
+
+    /// This function should return the root of the repository `repo_path` belongs to.
+    ///
+    /// It defaults to `repo_path`.
+    #[allow(clippy::missing_errors_doc)]
+    fn root<P>(&self, repo_path: P) -> Result<PathBuf, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(repo_path.as_ref().to_path_buf())
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Section {
-    pub file_name: String,
+    /// The path of the file, relative to the repository root
+    pub file_name: PathBuf,
     pub line_start: u32,
     pub line_end: u32,
 }
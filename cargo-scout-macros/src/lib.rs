@@ -1,39 +1,58 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static LOGS_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Prints the messages of the macros to stderr instead of stdout,
+/// when stdout is used for the output of a command.
+pub fn set_logs_to_stderr(logs_to_stderr: bool) {
+    LOGS_TO_STDERR.store(logs_to_stderr, Ordering::Relaxed);
+}
+
+#[doc(hidden)]
+pub fn log(message: impl std::fmt::Display) {
+    if LOGS_TO_STDERR.load(Ordering::Relaxed) {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+}
+
 #[macro_export(local_inner_macros)]
 macro_rules! success {
     ($value:expr) => {
-        std::println!("{}", $value.green());
+        $crate::log($value.green());
     };
     ($format:expr, $($arg:tt)+) => {
-        std::println!("{}", std::format!($format, $($arg)+).green());
+        $crate::log(std::format!($format, $($arg)+).green());
     };
 }
 
 #[macro_export(local_inner_macros)]
 macro_rules! info {
     ($value:expr) => {
-        std::println!("{}", $value.cyan());
+        $crate::log($value.cyan());
     };
     ($format:expr, $($arg:tt)+) => {
-        std::println!("{}", std::format!($format, $($arg)+).cyan());
+        $crate::log(std::format!($format, $($arg)+).cyan());
     };
 }
 
 #[macro_export(local_inner_macros)]
 macro_rules! warn {
     ($value:expr) => {
-        std::println!("{}", $value.yellow());
+        $crate::log($value.yellow());
     };
     ($format:expr, $($arg:tt)+) => {
-        std::println!("{}", std::format!($format, $($arg)+).yellow());
+        $crate::log(std::format!($format, $($arg)+).yellow());
     };
 }
 
 #[macro_export(local_inner_macros)]
 macro_rules! error {
     ($value:expr) => {
-        std::println!("{}", $value.red());
+        $crate::log($value.red());
     };
     ($format:expr, $($arg:tt)+) => {
-        std::println!("{}", std::format!($format, $($arg)+).red());
+        $crate::log(std::format!($format, $($arg)+).red());
     };
 }
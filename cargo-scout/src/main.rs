@@ -1,12 +1,20 @@
-use cargo_scout_lib::config::rust::CargoConfig;
+use cargo_scout_lib::config::file::ConfigFile;
+use cargo_scout_lib::config::metadata::MetadataConfig;
+use cargo_scout_lib::config::Config;
+use cargo_scout_lib::filter::generated::GeneratedFiles;
+use cargo_scout_lib::filter::PathFilter;
+use cargo_scout_lib::fix;
 use cargo_scout_lib::linter::clippy::Clippy;
 use cargo_scout_lib::linter::rustfmt::RustFmt;
-use cargo_scout_lib::linter::Lint;
-use cargo_scout_lib::scout::Scout;
-use cargo_scout_lib::vcs::git::Git;
+use cargo_scout_lib::linter::toolchain::Toolchain;
+use cargo_scout_lib::linter::{Level, Lint};
+use cargo_scout_lib::scout::{Failure, Report, Scout};
+use cargo_scout_lib::vcs::git::{Author, Git, TimeWindow};
 use cargo_scout_lib::Error;
-use cargo_scout_macros::{success, warn};
+use cargo_scout_macros::{error, info, success, warn};
 use colored::Colorize;
+use std::path::{Path, PathBuf};
+use structopt::clap::ErrorKind;
 use structopt::StructOpt;
 
 #[derive(StructOpt)]
@@ -16,26 +24,73 @@ use structopt::StructOpt;
     about = "Leave the codebase better than when you found it."
 )]
 enum Command {
+    Fix(FixOptions),
     Fmt(FmtOptions),
     Lint(LintOptions),
 }
 
 #[derive(Debug, StructOpt)]
+struct FixOptions {
+    #[structopt(long = "dry-run", conflicts_with = "patch")]
+    /// Print the fixes as a patch instead of applying them
+    dry_run: bool,
+    #[structopt(long = "patch", value_name = "file", parse(from_os_str))]
+    /// Write the fixes as a patch to this file (`-` for stdout) instead of applying them
+    patch: Option<PathBuf>,
+    #[structopt(flatten)]
+    lint: LintOptions,
+}
+
+#[derive(Debug, StructOpt)]
+#[allow(clippy::struct_excessive_bools)]
 struct FmtOptions {
-    #[structopt(
-        short = "b",
-        long = "branch",
-        value_name = "branch",
-        default_value = "HEAD"
-    )]
-    /// Set the target branch
-    branch: String,
-    #[structopt(short = "t", long = "cargo-toml", default_value = "./Cargo.toml")]
-    /// Pass the path of the `Cargo.toml` file
-    cargo_toml: String,
+    #[structopt(short = "v", long = "verbose")]
+    /// Set the verbosity level
+    verbose: bool,
+    #[structopt(short = "b", long = "branch", value_name = "branch")]
+    /// Set the target branch (defaults to HEAD)
+    branch: Option<String>,
+    #[structopt(short = "t", long = "cargo-toml")]
+    /// Pass the path of the `Cargo.toml` file (defaults to the closest one in the current directory or its parents)
+    cargo_toml: Option<String>,
+    #[structopt(short = "C", value_name = "dir", parse(from_os_str))]
+    /// Run as if cargo-scout was started in this directory
+    directory: Option<PathBuf>,
     #[structopt(short = "w", long = "without-error")]
     /// Set to display the warnings without actually returning an error
     without_error: bool,
+    #[structopt(long = "toolchain", value_name = "toolchain")]
+    /// Run cargo with this toolchain, such as `nightly-2020-01-01` (defaults to the one selected by rustup)
+    toolchain: Option<String>,
+    #[structopt(long = "keep-going")]
+    /// Keep checking the other packages when one of them fails, and report the failures at the end
+    keep_going: bool,
+    #[structopt(long = "write")]
+    /// Fix the formatting of the changed lines, leaving the rest of the files untouched
+    write: bool,
+    #[structopt(
+        long = "patch",
+        value_name = "file",
+        parse(from_os_str),
+        conflicts_with = "write"
+    )]
+    /// Write the formatting fixes as a patch to this file (`-` for stdout) instead of applying them
+    patch: Option<PathBuf>,
+    #[structopt(long = "config-path", value_name = "path", parse(from_os_str))]
+    /// Use this rustfmt configuration file instead of the `rustfmt.toml` files of the project
+    config_path: Option<PathBuf>,
+    #[structopt(long = "edition", value_name = "edition")]
+    /// Format with this edition instead of the edition of each crate
+    edition: Option<String>,
+    #[structopt(long = "config", value_name = "key=value", number_of_values = 1)]
+    /// Pass a rustfmt option, such as `max_width=120` (can be passed several times)
+    config: Vec<String>,
+    #[structopt(flatten)]
+    packages: PackageOptions,
+    #[structopt(flatten)]
+    filter: FilterOptions,
+    #[structopt(flatten)]
+    diff: DiffOptions,
 }
 
 #[derive(Debug, StructOpt)]
@@ -53,64 +108,432 @@ struct LintOptions {
     #[structopt(long = "features")]
     /// Pass features to clippy
     features: Option<String>,
-    #[structopt(
-        short = "b",
-        long = "branch",
-        value_name = "branch",
-        default_value = "HEAD"
-    )]
-    /// Set the target branch
-    branch: String,
+    #[structopt(short = "b", long = "branch", value_name = "branch")]
+    /// Set the target branch (defaults to HEAD)
+    branch: Option<String>,
 
-    #[structopt(short = "t", long = "cargo-toml", default_value = "./Cargo.toml")]
-    /// Pass the path of the `Cargo.toml` file
-    cargo_toml: String,
+    #[structopt(short = "t", long = "cargo-toml")]
+    /// Pass the path of the `Cargo.toml` file (defaults to the closest one in the current directory or its parents)
+    cargo_toml: Option<String>,
+    #[structopt(short = "C", value_name = "dir", parse(from_os_str))]
+    /// Run as if cargo-scout was started in this directory
+    directory: Option<PathBuf>,
     #[structopt(short = "w", long = "without-error")]
     /// Set to display the warnings without actually returning an error
     without_error: bool,
+    #[structopt(long = "toolchain", value_name = "toolchain")]
+    /// Run cargo with this toolchain, such as `nightly-2020-01-01` (defaults to the one selected by rustup)
+    toolchain: Option<String>,
+    #[structopt(long = "keep-going")]
+    /// Keep checking the other packages when one of them fails, and report the failures at the end
+    keep_going: bool,
     #[structopt(short = "p", long = "preview")]
     /// Enable nightly features (e.g. get lints even after the build has already been done.)
     preview: bool,
+    #[structopt(long = "workspace-run")]
+    /// Run clippy once for all the packages with changes, instead of once per package
+    workspace_run: bool,
+    #[structopt(short = "j", long = "jobs", value_name = "N", default_value = "1")]
+    /// Lint up to N packages at once (in a single clippy run, unless --isolated-target-dirs is set)
+    jobs: usize,
+    #[structopt(long = "isolated-target-dirs")]
+    /// Give each package linted at once its own target directory, building its dependencies again
+    isolated_target_dirs: bool,
+    #[structopt(flatten)]
+    packages: PackageOptions,
+    #[structopt(flatten)]
+    targets: TargetOptions,
+    #[structopt(flatten)]
+    filter: FilterOptions,
+    #[structopt(flatten)]
+    diff: DiffOptions,
+}
+
+#[derive(Debug, StructOpt)]
+#[allow(clippy::struct_excessive_bools)]
+struct TargetOptions {
+    #[structopt(long = "all-targets")]
+    /// Check all targets (default)
+    all_targets: bool,
+    #[structopt(long = "lib")]
+    /// Check the library
+    lib: bool,
+    #[structopt(long = "bins")]
+    /// Check all binaries
+    bins: bool,
+    #[structopt(long = "examples")]
+    /// Check all examples
+    examples: bool,
+    #[structopt(long = "tests")]
+    /// Check all tests
+    tests: bool,
+    #[structopt(long = "benches")]
+    /// Check all benches
+    benches: bool,
+}
+
+impl TargetOptions {
+    fn targets(&self) -> Vec<String> {
+        let targets = [
+            (self.all_targets, "all-targets"),
+            (self.lib, "lib"),
+            (self.bins, "bins"),
+            (self.examples, "examples"),
+            (self.tests, "tests"),
+            (self.benches, "benches"),
+        ];
+        targets
+            .iter()
+            .filter(|(enabled, _)| *enabled)
+            .map(|(_, target)| (*target).to_string())
+            .collect()
+    }
 }
 
+#[derive(Debug, StructOpt)]
+struct DiffOptions {
+    #[structopt(long = "author", value_name = "email")]
+    #[allow(clippy::option_option)]
+    /// Only check the lines written by this author (defaults to git's `user.email`)
+    author: Option<Option<String>>,
+    #[structopt(
+        long = "since",
+        value_name = "date",
+        conflicts_with_all = &["last-commits", "branch"]
+    )]
+    /// Check the lines changed since a date (YYYY-MM-DD, or e.g. `2 weeks`) instead of a diff with the target branch
+    since: Option<String>,
+    #[structopt(long = "last-commits", value_name = "count", conflicts_with = "branch")]
+    /// Check the lines changed by the last commits instead of a diff with the target branch
+    last_commits: Option<usize>,
+}
+
+impl DiffOptions {
+    fn author(&self) -> Option<Author> {
+        match &self.author {
+            Some(Some(email)) => Some(Author::Email(email.clone())),
+            Some(None) => Some(Author::CurrentUser),
+            None => None,
+        }
+    }
+
+    fn time_window(&self) -> Result<Option<TimeWindow>, Error> {
+        match (&self.since, self.last_commits) {
+            (Some(date), _) => Ok(Some(TimeWindow::since(date)?)),
+            (None, Some(count)) => Ok(Some(TimeWindow::LastCommits(count))),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+struct PackageOptions {
+    // No `-p` short flag, `-p` is `--preview`
+    #[structopt(long = "package", value_name = "name", number_of_values = 1)]
+    /// Only check the package with this exact name (can be passed several times)
+    package: Vec<String>,
+    #[structopt(long = "exclude", value_name = "name", number_of_values = 1)]
+    /// Never check the package with this exact name (can be passed several times)
+    exclude: Vec<String>,
+}
+
+#[derive(Debug, StructOpt)]
+struct FilterOptions {
+    #[structopt(long = "include-path", value_name = "glob", number_of_values = 1)]
+    /// Only check files matching this glob (can be passed several times)
+    include_path: Vec<String>,
+    #[structopt(long = "exclude-path", value_name = "glob", number_of_values = 1)]
+    /// Never check files matching this glob (can be passed several times)
+    exclude_path: Vec<String>,
+}
+
+// The exit statuses of cargo-scout, documented in the README,
+// so CI can tell lints apart from a broken setup.
+// It exits with 0 when no lints were found.
+/// Lints or compilation errors were found
+const EXIT_LINTS: i32 = 1;
+/// The command line, the configuration or the repository is invalid
+const EXIT_CONFIG: i32 = 2;
+/// A tool could not be run, or failed to lint some packages
+const EXIT_TOOL_FAILURE: i32 = 3;
+
 // There is no logic to test
 #[cfg(not(tarpaulin_include))]
-fn main() -> Result<(), Error> {
-    match Command::from_args() {
+fn main() {
+    let matches = Command::clap()
+        .get_matches_safe()
+        .unwrap_or_else(|e| match e.kind {
+            ErrorKind::HelpDisplayed | ErrorKind::VersionDisplayed => e.exit(),
+            _ => {
+                eprintln!("{}", e.message);
+                std::process::exit(EXIT_CONFIG);
+            }
+        });
+    let result = match Command::from_clap(&matches) {
+        Command::Fix(opts) => run_fix(opts),
         Command::Fmt(opts) => run_fmt(opts),
         Command::Lint(opts) => run_lint(opts),
+    };
+    if let Err(e) = result {
+        display_error(&e);
+        std::process::exit(exit_code(&e));
+    }
+}
+
+// Every error is mapped explicitly, so new ones get an exit status on purpose
+fn exit_code(e: &Error) -> i32 {
+    match e {
+        Error::NotClean | Error::Compilation(_) => EXIT_LINTS,
+        Error::ScoutBuilder
+        | Error::CargoToml(_)
+        | Error::Git(_)
+        | Error::Glob(_)
+        | Error::InvalidDate(_)
+        | Error::Toml(_)
+        | Error::UnknownPackage(_)
+        | Error::InvalidRevision(_)
+        | Error::ManifestNotFound(_)
+        | Error::InvalidManifest(_)
+        | Error::InvalidPath { .. } => EXIT_CONFIG,
+        Error::Command(_)
+        | Error::Utf8(_)
+        | Error::Json(_)
+        | Error::Io(_)
+        | Error::Replacement(_)
+        | Error::MemberFailures(_)
+        | Error::ToolNotFound(_)
+        | Error::ComponentMissing { .. }
+        | Error::ToolchainMissing(_) => EXIT_TOOL_FAILURE,
+    }
+}
+
+// The lints have already been displayed
+fn display_error(e: &Error) {
+    if let Error::NotClean = e {
+        return;
+    }
+    for l in e.to_string().split('\n') {
+        error!(l);
+    }
+    if let Some(hint) = e.hint() {
+        info!("hint: {}", hint);
     }
 }
 
 #[cfg(not(tarpaulin_include))]
 fn run_lint(opts: LintOptions) -> Result<(), Error> {
     let fail_if_errors = opts.without_error;
+    let report = clippy_scout(opts)?.report()?;
+    return_report(&report, fail_if_errors)
+}
+
+// Only the machine applicable suggestions of clippy are applied,
+// and only where they don't touch lines outside of the diff.
+#[cfg(not(tarpaulin_include))]
+fn run_fix(opts: FixOptions) -> Result<(), Error> {
+    let patch = if opts.dry_run {
+        Some(PathBuf::from("-"))
+    } else {
+        opts.patch
+    };
+    log_to_stderr_if_stdout(patch.as_deref());
+    let scout = clippy_scout(opts.lint)?;
+    let report = scout.report()?;
+    let relevant_lints = fix::in_sections(&report.lints, &report.sections);
+    let repo_root = scout.repo_root()?;
+    if let Some(patch) = &patch {
+        write_patch(&repo_root, &relevant_lints, patch)?;
+    } else {
+        write_fixes(&repo_root, &relevant_lints)?;
+    }
+    return_failures(&report.failures)
+}
 
-    let vcs = Git::with_target(opts.branch);
-    let config = CargoConfig::from_manifest_path(opts.cargo_toml)?;
+#[cfg(not(tarpaulin_include))]
+fn clippy_scout(opts: LintOptions) -> Result<Scout<Git, MetadataConfig, Clippy>, Error> {
+    enter_directory(opts.directory.as_deref())?;
+    let mut vcs = Git::with_target(opts.branch.unwrap_or_else(|| "HEAD".to_string()));
+    vcs.set_author(opts.diff.author())
+        .set_time_window(opts.diff.time_window()?);
+    let mut config = MetadataConfig::from_manifest_path_with_toolchain(
+        manifest_path(opts.cargo_toml)?,
+        &toolchain(opts.toolchain.clone()),
+    )?;
+    select_packages(&mut config, &opts.packages)?;
+    let config_file = config_file(config.root(), opts.filter)?;
     let mut linter = Clippy::default();
     linter
         .set_verbose(opts.verbose)
         .set_no_default_features(opts.no_default_features)
         .set_all_features(opts.all_features)
         .set_features(opts.features)
-        .set_preview(opts.preview);
-    let scout = Scout::new(vcs, config, linter);
-    let relevant_lints = scout.run()?;
-    return_warnings(&relevant_lints, fail_if_errors)
+        .set_preview(opts.preview)
+        .set_toolchain(toolchain(opts.toolchain))
+        .set_workspace_run(opts.workspace_run)
+        .set_jobs(opts.jobs)
+        .set_isolated_target_dir(
+            opts.isolated_target_dirs
+                .then(|| config.target_dir().join("scout")),
+        )
+        .set_targets(&opts.targets.targets())
+        .set_member_configs(config_file.members.clone());
+    let mut scout = Scout::new(vcs, config, linter);
+    scout
+        .set_filter(PathFilter::new(&config_file.include, &config_file.exclude)?)
+        .set_generated(GeneratedFiles::new(&config_file.generated)?)
+        .set_verbose(opts.verbose)
+        .set_keep_going(opts.keep_going);
+    Ok(scout)
 }
 
 #[cfg(not(tarpaulin_include))]
 fn run_fmt(opts: FmtOptions) -> Result<(), Error> {
+    log_to_stderr_if_stdout(opts.patch.as_deref());
+    enter_directory(opts.directory.as_deref())?;
     let fail_if_errors = opts.without_error;
 
-    let vcs = Git::with_target(opts.branch);
-    let config = CargoConfig::from_manifest_path(opts.cargo_toml)?;
-    let linter = RustFmt::default();
+    let mut vcs = Git::with_target(opts.branch.unwrap_or_else(|| "HEAD".to_string()));
+    vcs.set_author(opts.diff.author())
+        .set_time_window(opts.diff.time_window()?);
+    let mut config = MetadataConfig::from_manifest_path_with_toolchain(
+        manifest_path(opts.cargo_toml)?,
+        &toolchain(opts.toolchain.clone()),
+    )?;
+    select_packages(&mut config, &opts.packages)?;
+    let config_file = config_file(config.root(), opts.filter)?;
+    // Command line options win over the configuration file ones
+    let rustfmt_config = &config_file.rustfmt;
+    let config_path = match opts.config_path {
+        Some(path) => Some(std::env::current_dir()?.join(path)),
+        None => rustfmt_config
+            .config_path
+            .as_ref()
+            .map(|path| config.root().join(path)),
+    };
+    let mut rustfmt_options = rustfmt_config.config.clone();
+    rustfmt_options.extend(opts.config);
+    let mut linter = RustFmt::default();
+    linter
+        .set_toolchain(toolchain(opts.toolchain))
+        .set_config_path(config_path)
+        .set_edition(opts.edition.or_else(|| rustfmt_config.edition.clone()))
+        .set_config(&rustfmt_options);
 
-    let scout = Scout::new(vcs, config, linter);
-    let relevant_lints = scout.run()?;
-    return_warnings(&relevant_lints, fail_if_errors)
+    let mut scout = Scout::new(vcs, config, linter);
+    scout
+        .set_filter(PathFilter::new(&config_file.include, &config_file.exclude)?)
+        .set_generated(GeneratedFiles::new(&config_file.generated)?)
+        .set_verbose(opts.verbose)
+        .set_keep_going(opts.keep_going);
+    let report = scout.report()?;
+    if opts.write || opts.patch.is_some() {
+        // A mismatch may span changed and unchanged lines
+        let lints = fix::in_sections(&report.lints, &report.sections);
+        if let Some(patch) = &opts.patch {
+            write_patch(&scout.repo_root()?, &lints, patch)?;
+        } else {
+            write_fixes(&scout.repo_root()?, &lints)?;
+        }
+        return return_failures(&report.failures);
+    }
+    return_report(&report, fail_if_errors)
+}
+
+#[cfg(not(tarpaulin_include))]
+fn write_fixes(repo_root: &Path, lints: &[Lint]) -> Result<(), Error> {
+    let changed_files = fix::apply(repo_root, lints)?;
+    for file in &changed_files {
+        info!("[Fix] - fixed {}", file.display());
+    }
+    let fixed_lints = lints.iter().filter(|l| !l.replacements.is_empty()).count();
+    success!(
+        "Fixed {} issue(s) in {} file(s), you're good to go!",
+        fixed_lints,
+        changed_files.len()
+    );
+    Ok(())
+}
+
+// A patch written to stdout is the output of the command,
+// so the logs mustn't end up in it.
+fn log_to_stderr_if_stdout(patch: Option<&Path>) {
+    if patch == Some(Path::new("-")) {
+        cargo_scout_macros::set_logs_to_stderr(true);
+    }
+}
+
+// The patch paths are relative to the repository root,
+// so it can be applied with `git apply` from there.
+#[cfg(not(tarpaulin_include))]
+fn write_patch(repo_root: &Path, lints: &[Lint], patch_path: &Path) -> Result<(), Error> {
+    let fixed_files = fix::fix(repo_root, lints)?;
+    let patch = fix::patch(&fixed_files);
+    if patch_path == Path::new("-") {
+        print!("{}", patch);
+        return Ok(());
+    }
+    std::fs::write(patch_path, patch)?;
+    success!(
+        "Wrote the fixes of {} file(s) to {}",
+        fixed_files.len(),
+        patch_path.display()
+    );
+    Ok(())
+}
+
+#[cfg(not(tarpaulin_include))]
+fn enter_directory(dir: Option<&Path>) -> Result<(), Error> {
+    match dir {
+        Some(dir) => std::env::set_current_dir(dir).map_err(|source| Error::InvalidPath {
+            path: dir.to_path_buf(),
+            source,
+        }),
+        None => Ok(()),
+    }
+}
+
+// Like cargo, look for the closest `Cargo.toml` file
+// when none has been provided.
+#[cfg(not(tarpaulin_include))]
+fn manifest_path(cargo_toml: Option<String>) -> Result<PathBuf, Error> {
+    if let Some(cargo_toml) = cargo_toml {
+        return Ok(PathBuf::from(cargo_toml));
+    }
+    let current_dir = std::env::current_dir()?;
+    Ok(current_dir
+        .ancestors()
+        .map(|dir| dir.join("Cargo.toml"))
+        .find(|manifest| manifest.is_file())
+        .unwrap_or_else(|| PathBuf::from("Cargo.toml")))
+}
+
+fn toolchain(name: Option<String>) -> Toolchain {
+    let mut toolchain = Toolchain::default();
+    toolchain.set_name(name);
+    toolchain
+}
+
+#[cfg(not(tarpaulin_include))]
+fn select_packages(config: &mut MetadataConfig, opts: &PackageOptions) -> Result<(), Error> {
+    let skipped = config.select_packages(&opts.package, &opts.exclude)?;
+    if !skipped.is_empty() {
+        info!(
+            "[Config] - skipping {} member(s): {}",
+            skipped.len(),
+            skipped.join(", ")
+        );
+    }
+    Ok(())
+}
+
+// The configuration file lives next to the workspace `Cargo.toml` file.
+// Command line patterns are added to the ones it contains.
+#[cfg(not(tarpaulin_include))]
+fn config_file(project_dir: impl AsRef<Path>, opts: FilterOptions) -> Result<ConfigFile, Error> {
+    let mut config_file = ConfigFile::discover(project_dir)?;
+    config_file.include.extend(opts.include_path);
+    config_file.exclude.extend(opts.exclude_path);
+    Ok(config_file)
 }
 
 fn return_warnings(lints: &[Lint], without_error: bool) -> Result<(), Error> {
@@ -127,6 +550,59 @@ fn return_warnings(lints: &[Lint], without_error: bool) -> Result<(), Error> {
     }
 }
 
+// Compilation errors fail the run, even without error,
+// since the code can't be checked.
+// Members failing to lint come first, as the report is incomplete.
+fn return_report(report: &Report, without_error: bool) -> Result<(), Error> {
+    let lints_result = return_warnings(&report.lints, without_error);
+    // An error is reported once for each of its spans in the diff
+    let mut errors_in_diff: Vec<&str> = report
+        .lints
+        .iter()
+        .filter(|l| l.level == Level::Error)
+        .map(|l| l.message.as_str())
+        .collect();
+    errors_in_diff.sort_unstable();
+    errors_in_diff.dedup();
+    let compilation_errors = report.errors.len() + errors_in_diff.len();
+    if !report.errors.is_empty() {
+        display_errors(&report.errors);
+    }
+    return_failures(&report.failures)?;
+    if compilation_errors > 0 {
+        return Err(Error::Compilation(compilation_errors));
+    }
+    lints_result
+}
+
+fn return_failures(failures: &[Failure]) -> Result<(), Error> {
+    if failures.is_empty() {
+        return Ok(());
+    }
+    for f in failures {
+        error!("[Scout] - {} failed to lint:", f.member);
+        for l in f.error.split('\n') {
+            error!(l);
+        }
+        if let Some(hint) = &f.hint {
+            info!("hint: {}", hint);
+        }
+    }
+    Err(Error::MemberFailures(failures.len()))
+}
+
+fn display_errors(errors: &[Lint]) {
+    for e in errors {
+        for l in e.message.split('\n') {
+            error!(l);
+        }
+    }
+    error!(
+        "The code doesn't compile: {} error(s) outside of your diff",
+        errors.len()
+    );
+}
+
 fn display_warnings(warnings: &[Lint]) {
     for w in warnings {
         for l in w.message.split('\n') {
@@ -143,16 +619,20 @@ fn display_warnings(warnings: &[Lint]) {
 
 #[cfg(test)]
 mod tests {
-    use super::{return_warnings, Lint};
+    use super::{
+        exit_code, return_failures, return_report, return_warnings, Error, Failure, Level, Lint,
+        PathBuf, Report, EXIT_CONFIG, EXIT_LINTS, EXIT_TOOL_FAILURE,
+    };
     use cargo_scout_lib::linter::Location;
     #[test]
     fn test_return_status_with_lints() {
         let lints = vec![Lint {
             message: String::new(),
             location: Location {
-                path: String::new(),
+                path: PathBuf::new(),
                 lines: [0, 0],
             },
+            ..Lint::default()
         }];
 
         assert!(return_warnings(&lints, true).is_ok());
@@ -166,4 +646,101 @@ mod tests {
         assert!(return_warnings(&lints, true).is_ok());
         assert!(return_warnings(&lints, false).is_ok());
     }
+
+    #[test]
+    fn test_return_status_with_errors() {
+        let lint = |level: Level| Lint {
+            message: String::new(),
+            location: Location {
+                path: PathBuf::new(),
+                lines: [0, 0],
+            },
+            level,
+            ..Lint::default()
+        };
+        let report = Report {
+            lints: vec![lint(Level::Warning)],
+            ..Report::default()
+        };
+        assert!(return_report(&report, true).is_ok());
+
+        // The code doesn't compile, in the diff or elsewhere
+        let in_diff = Report {
+            lints: vec![lint(Level::Error)],
+            ..Report::default()
+        };
+        assert!(return_report(&in_diff, true).is_err());
+        let elsewhere = Report {
+            errors: vec![lint(Level::Error)],
+            ..Report::default()
+        };
+        assert!(return_report(&elsewhere, true).is_err());
+        // An error is counted once, whatever its number of spans
+        let spans = Report {
+            lints: vec![lint(Level::Error), lint(Level::Error)],
+            ..Report::default()
+        };
+        assert!(matches!(
+            return_report(&spans, true),
+            Err(Error::Compilation(1))
+        ));
+    }
+
+    #[test]
+    fn test_return_status_with_failures() {
+        let failure = Failure {
+            member: "foo".to_string(),
+            error: "Command error: clippy failed".to_string(),
+            hint: None,
+        };
+        let report = Report {
+            failures: vec![failure.clone()],
+            ..Report::default()
+        };
+        assert!(matches!(
+            return_report(&report, true),
+            Err(Error::MemberFailures(1))
+        ));
+        assert!(return_failures(&[]).is_ok());
+        assert!(matches!(
+            return_failures(&[failure.clone(), failure]),
+            Err(Error::MemberFailures(2))
+        ));
+    }
+
+    #[test]
+    fn test_exit_code() {
+        assert_eq!(EXIT_LINTS, exit_code(&Error::NotClean));
+        assert_eq!(EXIT_LINTS, exit_code(&Error::Compilation(1)));
+        assert_eq!(
+            EXIT_CONFIG,
+            exit_code(&Error::InvalidRevision("foo".to_string()))
+        );
+        assert_eq!(
+            EXIT_CONFIG,
+            exit_code(&Error::UnknownPackage("foo".to_string()))
+        );
+        assert_eq!(
+            EXIT_CONFIG,
+            exit_code(&Error::InvalidPath {
+                path: PathBuf::from("missing"),
+                source: std::io::ErrorKind::NotFound.into(),
+            })
+        );
+        assert_eq!(
+            EXIT_CONFIG,
+            exit_code(&Error::InvalidManifest(
+                "failed to parse manifest".to_string()
+            ))
+        );
+        assert_eq!(
+            EXIT_TOOL_FAILURE,
+            exit_code(&Error::Command("clippy failed".to_string()))
+        );
+        assert_eq!(
+            EXIT_TOOL_FAILURE,
+            exit_code(&Error::ToolNotFound("cargo".to_string()))
+        );
+        assert_eq!(EXIT_TOOL_FAILURE, exit_code(&Error::MemberFailures(2)));
+    }
 }